@@ -0,0 +1,34 @@
+// Screen-door dithering for LOD cross-fades
+// The fade is packed into the mesh's `MeshTag` by `LODFade::mesh_tag`
+#define_import_path lod_system::dither
+
+const LOD_FADE_ACTIVE_BIT: u32 = 0x40000000u;
+const LOD_FADE_OUT_BIT: u32 = 0x80000000u;
+const LOD_FADE_PROGRESS_MASK: u32 = 0xFFFFu;
+
+// 4x4 Bayer threshold in [0, 1)
+fn lod_dither_threshold(frag_coord: vec2<f32>) -> f32 {
+    var bayer = array<u32, 16>(
+        0u, 8u, 2u, 10u,
+        12u, 4u, 14u, 6u,
+        3u, 11u, 1u, 9u,
+        15u, 7u, 13u, 5u,
+    );
+    let p = vec2<u32>(frag_coord) % 4u;
+    return (f32(bayer[p.y * 4u + p.x]) + 0.5) / 16.0;
+}
+
+// True if the fragment is hidden by the fade in `tag`. Fading in and fading out
+// use complementary thresholds, so two representations fading in opposite
+// directions never overlap or leave holes.
+fn lod_fade_discard(frag_coord: vec2<f32>, tag: u32) -> bool {
+    if (tag & LOD_FADE_ACTIVE_BIT) == 0u {
+        return false;
+    }
+    let progress = f32(tag & LOD_FADE_PROGRESS_MASK) / f32(LOD_FADE_PROGRESS_MASK);
+    let threshold = lod_dither_threshold(frag_coord);
+    if (tag & LOD_FADE_OUT_BIT) != 0u {
+        return threshold < progress;
+    }
+    return threshold >= progress;
+}
@@ -0,0 +1,45 @@
+// Prepass and shadow pass of `LODDitherMaterial`, so depth, normals and shadows
+// fade with the main pass. The material is masked while it fades, which is what
+// makes bevy run a fragment shader in these passes.
+#import bevy_pbr::{
+    prepass_io::VertexOutput,
+    pbr_prepass_functions,
+    mesh_functions,
+}
+#ifdef PREPASS_FRAGMENT
+#import bevy_pbr::prepass_io::FragmentOutput
+#endif
+#import lod_system::dither::lod_fade_discard
+
+#ifdef PREPASS_FRAGMENT
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    if lod_fade_discard(in.position.xy, mesh_functions::get_tag(in.instance_index)) {
+        discard;
+    }
+    pbr_prepass_functions::prepass_alpha_discard(in);
+
+    var out: FragmentOutput;
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.frag_depth = in.unclipped_depth;
+#endif
+#ifdef NORMAL_PREPASS
+    // Normal maps are left out for the length of the fade
+    let normal = normalize(select(-in.world_normal, in.world_normal, is_front));
+    out.normal = vec4<f32>(normal * 0.5 + vec3<f32>(0.5), 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    out.motion_vector = pbr_prepass_functions::calculate_motion_vector(in.world_position, in.previous_world_position);
+#endif
+    return out;
+}
+#else
+// Depth only, e.g. shadow maps
+@fragment
+fn fragment(in: VertexOutput) {
+    if lod_fade_discard(in.position.xy, mesh_functions::get_tag(in.instance_index)) {
+        discard;
+    }
+    pbr_prepass_functions::prepass_alpha_discard(in);
+}
+#endif
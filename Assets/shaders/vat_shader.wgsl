@@ -1,20 +1,63 @@
 // Vertex Animation Texture (VAT) Shader
-// This shader reads vertex positions and normals from textures to animate meshes
+// Reads vertex positions and normals baked by `bake_vat` and lights them like a
+// StandardMaterial. The same file is used as the prepass shader, so depth,
+// normals and shadows follow the animation and the LOD fade too. With `VAT_INSTANCED`
+// (`VATInstancedMaterial`) the playback is read per instance from a storage
+// buffer instead of the material uniform. `VAT_REST_DELTA` and
+// `VAT_NORMALS_OCTAHEDRAL` follow the VAT's `VATEncoding`.
 
-struct VertexInput {
-    @builtin(vertex_index) vertex_index: u32,
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>,
+#import bevy_pbr::{
+    mesh_bindings::mesh,
+    mesh_functions,
+    view_transformations::position_world_to_clip,
 }
 
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) world_position: vec3<f32>,
-    @location(1) world_normal: vec3<f32>,
-    @location(2) uv: vec2<f32>,
+#import lod_system::dither::lod_fade_discard
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::VertexOutput
+#ifdef PREPASS_FRAGMENT
+#import bevy_pbr::prepass_io::FragmentOutput
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+#import bevy_pbr::{
+    mesh_view_bindings::view,
+    prepass_bindings::previous_view_uniforms,
 }
+#endif
+#else
+#import bevy_pbr::{
+    forward_io::FragmentOutput,
+    mesh_view_bindings::view,
+    pbr_functions,
+    pbr_types,
+}
+#endif
 
+#ifdef VAT_INSTANCED
+struct VATUniforms {
+    total_frames: f32,
+    vertex_count: u32,
+    texture_width: u32,
+    bbox_min: vec3<f32>,
+    bbox_max: vec3<f32>,
+}
+
+// `VATInstanceData`, one per entity at the slot in its `MeshTag`
+struct VATInstance {
+    current_frame: f32,
+    clip_start: u32,
+    clip_frame_count: u32,
+    // Frames played since the last frame, for motion vectors
+    elapsed_frames: f32,
+    previous_frame: f32,
+    previous_clip_start: u32,
+    previous_clip_frame_count: u32,
+    clip_blend: f32,
+    // `LODFade::mesh_tag`
+    fade: u32,
+}
+#else
 struct VATUniforms {
     // Animation parameters
     current_frame: f32,
@@ -24,105 +67,284 @@ struct VATUniforms {
     // Bounding box for position reconstruction
     bbox_min: vec3<f32>,
     bbox_max: vec3<f32>,
+    // Frame range of the playing clip, `current_frame` counts from its start
+    clip_start: u32,
+    clip_frame_count: u32,
+    // The clip being blended out, weighted by `1 - clip_blend`
+    previous_clip_start: u32,
+    previous_clip_frame_count: u32,
+    previous_frame: f32,
+    clip_blend: f32,
 }
+#endif
 
-@group(0) @binding(0) var<uniform> view: View;
-@group(1) @binding(0) var<uniform> mesh: Mesh;
+// Must match the `AsBindGroup` layout of `VATMaterial` and `VATInstancedMaterial`
 @group(2) @binding(0) var<uniform> vat: VATUniforms;
 @group(2) @binding(1) var position_texture: texture_2d<f32>;
 @group(2) @binding(2) var normal_texture: texture_2d<f32>;
-@group(2) @binding(3) var position_sampler: sampler;
-@group(2) @binding(4) var normal_sampler: sampler;
+@group(2) @binding(5) var base_color_texture: texture_2d<f32>;
+@group(2) @binding(6) var base_color_sampler: sampler;
+#ifdef VAT_INSTANCED
+@group(2) @binding(7) var<storage, read> instances: array<VATInstance>;
+#endif
 
-// Standard material bindings
-@group(3) @binding(0) var base_color_texture: texture_2d<f32>;
-@group(3) @binding(1) var base_color_sampler: sampler;
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @builtin(vertex_index) vertex_index: u32,
+#ifdef VAT_REST_DELTA
+    // Rest pose the baked offsets are relative to
+    @location(0) position: vec3<f32>,
+#endif
+#ifdef VERTEX_UVS_A
+#ifdef PREPASS_PIPELINE
+    @location(1) uv: vec2<f32>,
+#else
+    @location(2) uv: vec2<f32>,
+#endif
+#endif
+}
 
-fn get_vat_uv(vertex_id: u32, frame: f32) -> vec2<f32> {
-    let vertices_per_row = vat.texture_width;
-    let vertex_x = vertex_id % vertices_per_row;
-    let vertex_y = vertex_id / vertices_per_row;
-    
-    // Calculate UV coordinates for the current frame
-    let frame_offset = frame / vat.total_frames;
-    let u = (f32(vertex_x) + 0.5) / f32(vertices_per_row);
-    let v = (f32(vertex_y) + frame_offset) / f32(vat.total_frames);
-    
-    return vec2<f32>(u, v);
+#ifndef PREPASS_PIPELINE
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) @interpolate(flat) instance_index: u32,
 }
+#endif
 
-fn decode_position(encoded: vec3<f32>) -> vec3<f32> {
-    // Decode position from normalized texture values
-    let bbox_size = vat.bbox_max - vat.bbox_min;
-    return vat.bbox_min + encoded * bbox_size;
+struct VATSample {
+    position: vec3<f32>,
+    normal: vec3<f32>,
 }
 
-fn decode_normal(encoded: vec3<f32>) -> vec3<f32> {
-    // Decode normal from texture (stored as 0-1, needs to be -1 to 1)
-    return normalize(encoded * 2.0 - 1.0);
+// A frame of the playing clip, blended with a frame of the previous clip
+struct VATPlayback {
+    current_frame: f32,
+    clip_start: u32,
+    clip_frame_count: u32,
+    previous_frame: f32,
+    previous_clip_start: u32,
+    previous_clip_frame_count: u32,
+    clip_blend: f32,
+}
+
+fn vat_playback(instance_index: u32) -> VATPlayback {
+#ifdef VAT_INSTANCED
+    let instance = instances[mesh_functions::get_tag(instance_index)];
+    return VATPlayback(
+        instance.current_frame,
+        instance.clip_start,
+        instance.clip_frame_count,
+        instance.previous_frame,
+        instance.previous_clip_start,
+        instance.previous_clip_frame_count,
+        instance.clip_blend,
+    );
+#else
+    return VATPlayback(
+        vat.current_frame,
+        vat.clip_start,
+        vat.clip_frame_count,
+        vat.previous_frame,
+        vat.previous_clip_start,
+        vat.previous_clip_frame_count,
+        vat.clip_blend,
+    );
+#endif
+}
+
+// `LODFade::mesh_tag` of the instance, 0 when not fading
+fn vat_fade(instance_index: u32) -> u32 {
+#ifdef VAT_INSTANCED
+    return instances[mesh_functions::get_tag(instance_index)].fade;
+#else
+    return mesh_functions::get_tag(instance_index);
+#endif
+}
+
+// Texel of `vertex` in `frame`, see `VATFile` for the layout
+fn vat_texel(vertex: u32, frame: u32) -> vec2<u32> {
+    let rows_per_frame = (vat.vertex_count + vat.texture_width - 1u) / vat.texture_width;
+    return vec2<u32>(vertex % vat.texture_width, frame * rows_per_frame + vertex / vat.texture_width);
+}
+
+// Inverse of `octahedral_encode`, mirrored by `octahedral_decode`
+fn octahedral_decode(encoded: vec2<f32>) -> vec3<f32> {
+    let f = encoded * 2.0 - 1.0;
+    var n = vec3<f32>(f, 1.0 - abs(f.x) - abs(f.y));
+    let fold = max(-n.z, 0.0);
+    n.x += select(fold, -fold, n.x >= 0.0);
+    n.y += select(fold, -fold, n.y >= 0.0);
+    return normalize(n);
+}
+
+// `rest` is the vertex's rest position, only added with `VAT_REST_DELTA`
+fn decode_frame(texel: vec2<u32>, rest: vec3<f32>) -> VATSample {
+    var sample: VATSample;
+    sample.position = vat.bbox_min + textureLoad(position_texture, texel, 0).xyz * (vat.bbox_max - vat.bbox_min);
+#ifdef VAT_REST_DELTA
+    sample.position += rest;
+#endif
+#ifdef VAT_NORMALS_OCTAHEDRAL
+    sample.normal = octahedral_decode(textureLoad(normal_texture, texel, 0).xy);
+#else
+    sample.normal = normalize(textureLoad(normal_texture, texel, 0).xyz * 2.0 - 1.0);
+#endif
+    return sample;
+}
+
+fn blend_samples(a: VATSample, b: VATSample, blend: f32) -> VATSample {
+    let normal = mix(a.normal, b.normal, blend);
+    var sample: VATSample;
+    sample.position = mix(a.position, b.position, blend);
+    // Normalised lerp, keeping the first normal if the two cancel out
+    sample.normal = select(a.normal, normalize(normal), dot(normal, normal) > 1e-8);
+    return sample;
+}
+
+// Blends the two frames of a clip bracketing `frame`, wrapping from the last
+// frame of the clip to its first (and from before the first to the last)
+fn sample_clip(vertex: u32, rest: vec3<f32>, clip_start: u32, clip_frame_count: u32, frame: f32) -> VATSample {
+    let frame_count = max(clip_frame_count, 1u);
+    let clip_frame = frame - floor(frame / f32(frame_count)) * f32(frame_count);
+    let frame_a = u32(clip_frame) % frame_count;
+    let frame_b = (frame_a + 1u) % frame_count;
+    return blend_samples(
+        decode_frame(vat_texel(vertex, clip_start + frame_a), rest),
+        decode_frame(vat_texel(vertex, clip_start + frame_b), rest),
+        fract(clip_frame),
+    );
+}
+
+// Samples the playing clip, cross-faded with the previous one while
+// `clip_blend` is below 1. Mirrored on the CPU by `sample_vat_vertex`.
+fn sample_vat(vertex: u32, rest: vec3<f32>, playback: VATPlayback) -> VATSample {
+    let current = sample_clip(vertex, rest, playback.clip_start, playback.clip_frame_count, playback.current_frame);
+    if playback.clip_blend >= 1.0 {
+        return current;
+    }
+    let previous = sample_clip(vertex, rest, playback.previous_clip_start, playback.previous_clip_frame_count, playback.previous_frame);
+    return blend_samples(previous, current, playback.clip_blend);
 }
 
 @vertex
-fn vertex(input: VertexInput) -> VertexOutput {
-    var output: VertexOutput;
-    
-    // Calculate texture coordinates for VAT lookup
-    let vat_uv = get_vat_uv(input.vertex_index, vat.current_frame);
-    
-    // Sample position and normal from VAT textures
-    let encoded_position = textureSampleLevel(position_texture, position_sampler, vat_uv, 0.0).xyz;
-    let encoded_normal = textureSampleLevel(normal_texture, normal_sampler, vat_uv, 0.0).xyz;
-    
-    // Decode the values
-    let animated_position = decode_position(encoded_position);
-    let animated_normal = decode_normal(encoded_normal);
-    
-    // Transform to world space
-    let world_position = (mesh.model * vec4<f32>(animated_position, 1.0)).xyz;
-    let world_normal = normalize((mesh.model * vec4<f32>(animated_normal, 0.0)).xyz);
-    
-    // Calculate clip position
-    output.clip_position = view.view_proj * vec4<f32>(world_position, 1.0);
-    output.world_position = world_position;
-    output.world_normal = world_normal;
-    output.uv = input.uv;
-    
-    return output;
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    // The mesh shares its vertex buffer with other meshes
+    let vertex_id = vertex.vertex_index - mesh[vertex.instance_index].first_vertex_index;
+    let playback = vat_playback(vertex.instance_index);
+#ifdef VAT_REST_DELTA
+    let rest = vertex.position;
+#else
+    let rest = vec3<f32>(0.0);
+#endif
+    let sample = sample_vat(vertex_id, rest, playback);
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    out.world_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4<f32>(sample.position, 1.0));
+    out.position = position_world_to_clip(out.world_position.xyz);
+
+#ifdef PREPASS_PIPELINE
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.unclipped_depth = out.position.z;
+    out.position.z = min(out.position.z, 1.0);
+#endif
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef NORMAL_PREPASS_OR_DEFERRED_PREPASS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(sample.normal, vertex.instance_index);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+#ifdef VAT_INSTANCED
+    // Where the animation had the vertex a frame ago
+    let elapsed_frames = instances[mesh_functions::get_tag(vertex.instance_index)].elapsed_frames;
+    var previous_playback = playback;
+    previous_playback.current_frame -= elapsed_frames;
+    previous_playback.previous_frame -= elapsed_frames;
+    let previous_position = sample_vat(vertex_id, rest, previous_playback).position;
+#else
+    // Frame to frame motion of a shared material's animation is left out
+    let previous_position = sample.position;
+#endif
+    out.previous_world_position = mesh_functions::mesh_position_local_to_world(
+        mesh_functions::get_previous_world_from_local(vertex.instance_index),
+        vec4<f32>(previous_position, 1.0),
+    );
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+#else
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(sample.normal, vertex.instance_index);
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+    out.instance_index = vertex.instance_index;
+#endif
+
+    return out;
 }
 
+#ifdef PREPASS_PIPELINE
+// Only run while the material is masked, e.g. for an LOD fade, or when the
+// prepass writes normals or motion vectors
+#ifdef PREPASS_FRAGMENT
 @fragment
-fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
-    // Simple lighting calculation
-    let light_dir = normalize(vec3<f32>(1.0, 1.0, 1.0));
-    let n_dot_l = max(dot(input.world_normal, light_dir), 0.0);
-    let ambient = 0.2;
-    let diffuse = n_dot_l * 0.8;
-    
-    // Sample base color texture
-    let base_color = textureSample(base_color_texture, base_color_sampler, input.uv);
-    
-    // Apply lighting
-    let final_color = base_color.rgb * (ambient + diffuse);
-    
-    return vec4<f32>(final_color, base_color.a);
-}
-
-// Simplified VAT shader for lower LOD levels
-@vertex
-fn vertex_simple(input: VertexInput) -> VertexOutput {
-    var output: VertexOutput;
-    
-    // For lowest LOD, just use the first frame (static pose)
-    let vat_uv = get_vat_uv(input.vertex_index, 0.0);
-    
-    let encoded_position = textureSampleLevel(position_texture, position_sampler, vat_uv, 0.0).xyz;
-    let static_position = decode_position(encoded_position);
-    
-    let world_position = (mesh.model * vec4<f32>(static_position, 1.0)).xyz;
-    output.clip_position = view.view_proj * vec4<f32>(world_position, 1.0);
-    output.world_position = world_position;
-    output.world_normal = input.normal; // Use original normal for performance
-    output.uv = input.uv;
-    
-    return output;
+fn fragment(in: VertexOutput) -> FragmentOutput {
+    if lod_fade_discard(in.position.xy, vat_fade(in.instance_index)) {
+        discard;
+    }
+
+    var out: FragmentOutput;
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.frag_depth = in.unclipped_depth;
+#endif
+#ifdef NORMAL_PREPASS
+    // Single sided, like the main pass
+    let normal = normalize(in.world_normal);
+    out.normal = vec4<f32>(normal * 0.5 + vec3<f32>(0.5), 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    // As `pbr_prepass_functions::calculate_motion_vector`, which would import
+    // `StandardMaterial`'s bindings
+    let clip_position = view.unjittered_clip_from_world * in.world_position;
+    let previous_clip_position = previous_view_uniforms.clip_from_world * in.previous_world_position;
+    out.motion_vector = (clip_position.xy / clip_position.w - previous_clip_position.xy / previous_clip_position.w) * vec2<f32>(0.5, -0.5);
+#endif
+    return out;
+}
+#else
+// Depth only, e.g. shadow maps
+@fragment
+fn fragment(in: VertexOutput) {
+    if lod_fade_discard(in.position.xy, vat_fade(in.instance_index)) {
+        discard;
+    }
+}
+#endif
+#else
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    // Screen-door cross-fade between LOD representations
+    if lod_fade_discard(in.position.xy, vat_fade(in.instance_index)) {
+        discard;
+    }
+
+    var pbr_input = pbr_types::pbr_input_new();
+    pbr_input.material.base_color = textureSample(base_color_texture, base_color_sampler, in.uv);
+    pbr_input.frag_coord = in.position;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = pbr_functions::prepare_world_normal(in.world_normal, false, is_front);
+    pbr_input.N = normalize(pbr_input.world_normal);
+    pbr_input.is_orthographic = view.clip_from_view[3].w == 1.0;
+    pbr_input.V = pbr_functions::calculate_view(in.world_position, pbr_input.is_orthographic);
+
+    var out: FragmentOutput;
+    out.color = pbr_functions::apply_pbr_lighting(pbr_input);
+    out.color = pbr_functions::main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
 }
+#endif
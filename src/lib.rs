@@ -0,0 +1,3 @@
+//! The LOD system behind the rat demo, usable on its own
+
+pub mod lod_system;
@@ -0,0 +1,794 @@
+use bevy::prelude::*;
+use bevy::animation::graph::AnimationNodeType;
+use bevy::animation::{advance_animations, animate_targets};
+use bevy::app::{Animation, SpawnScene};
+use bevy::scene::scene_spawner;
+use bevy::platform::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::lod_system::core::LODStrategy;
+use crate::lod_system::strategies::{AnimationLODData, HybridLODData};
+
+// Animation time slicing, driven by `AnimationLODData::update_rate`
+
+/// LOD data that carries an `AnimationLODData`
+pub trait AnimationLODSource: Component {
+    fn animation_data(&self) -> &AnimationLODData;
+}
+
+impl AnimationLODSource for AnimationLODData {
+    fn animation_data(&self) -> &AnimationLODData {
+        self
+    }
+}
+
+impl AnimationLODSource for HybridLODData {
+    fn animation_data(&self) -> &AnimationLODData {
+        &self.animation_data
+    }
+}
+
+/// Which frames a time-sliced entity's animations are advanced and evaluated on
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct AnimationTimeSlice {
+    pub update_interval: u8,      // Frames between updates
+    pub frame_offset: u8,         // Stagger updates across entities
+    pub last_update_frame: u64,
+    pub accumulated_time: f32,    // Time skipped since the last update
+    pub step_time: f32,           // Time applied by the last update
+    pub frozen: bool,             // Hold the current pose until re-enabled
+}
+
+impl AnimationTimeSlice {
+    pub fn is_due(&self, frame: u64) -> bool {
+        if self.frozen {
+            return false;
+        }
+        self.update_interval <= 1
+            || (frame + self.frame_offset as u64).is_multiple_of(self.update_interval as u64)
+    }
+}
+
+/// Links an `AnimationPlayer` to the time-sliced `T` entity that owns it.
+///
+/// Scene players live on a descendant of the LOD entity, so the link is
+/// resolved once when the player is added.
+#[derive(Component, Debug)]
+pub struct TimeSlicedAnimationPlayer {
+    pub root: Entity,
+    evaluated: bool,
+    paused: Vec<AnimationNodeIndex>,
+    weights: Vec<(AnimationNodeIndex, f32)>,
+}
+
+impl TimeSlicedAnimationPlayer {
+    /// True if the scheduler is holding any of the player's animations
+    pub fn has_paused(&self) -> bool {
+        !self.paused.is_empty()
+    }
+}
+
+/// Buckets `T` entities into frame slots by animation update rate.
+///
+/// `update_rate` is converted into a frame interval against `reference_rate`,
+/// and entities sharing an interval are spread round-robin across its frame
+/// offsets so a bucket never updates all at once.
+#[derive(Resource)]
+pub struct AnimationScheduler<T> {
+    pub current_frame: u64,
+    pub reference_rate: f32,
+    pub frame_buckets: HashMap<u8, Vec<Entity>>,
+    next_offsets: HashMap<u8, u8>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Default for AnimationScheduler<T> {
+    fn default() -> Self {
+        Self::new(60.0)
+    }
+}
+
+impl<T> AnimationScheduler<T> {
+    pub fn new(reference_rate: f32) -> Self {
+        Self {
+            current_frame: 0,
+            reference_rate,
+            frame_buckets: HashMap::default(),
+            next_offsets: HashMap::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Frames between updates for `update_rate` updates per second
+    pub fn interval_for(&self, update_rate: f32) -> u8 {
+        if update_rate <= 0.0 {
+            return 1;
+        }
+        (self.reference_rate / update_rate).round().clamp(1.0, u8::MAX as f32) as u8
+    }
+
+    /// Number of entities updated every `interval` frames
+    pub fn bucket_len(&self, interval: u8) -> usize {
+        self.frame_buckets.get(&interval).map_or(0, Vec::len)
+    }
+
+    /// Moves an entity into the bucket for `interval`, returning its frame offset
+    fn insert(&mut self, entity: Entity, previous_interval: Option<u8>, interval: u8) -> u8 {
+        if let Some(previous) = previous_interval {
+            self.remove_from_bucket(entity, previous);
+        }
+        self.frame_buckets.entry(interval).or_default().push(entity);
+
+        let next = self.next_offsets.entry(interval).or_default();
+        let offset = *next;
+        *next = (*next + 1) % interval;
+        offset
+    }
+
+    fn remove_from_bucket(&mut self, entity: Entity, interval: u8) {
+        if let Some(bucket) = self.frame_buckets.get_mut(&interval) {
+            if let Some(index) = bucket.iter().position(|e| *e == entity) {
+                bucket.swap_remove(index);
+            }
+            if bucket.is_empty() {
+                self.frame_buckets.remove(&interval);
+                self.next_offsets.remove(&interval);
+            }
+        }
+    }
+}
+
+/// Time slices the animation players of `T` entities using the
+/// `AnimationLODData` of strategy `S`.
+///
+/// Players whose slot isn't due are paused and excluded from evaluation, and
+/// the skipped time is caught up on their next due frame. Entities whose
+/// animation is disabled with `AnimationLODData::frozen` keep their last pose
+/// and resume where they would have been had they kept playing.
+pub struct AnimationSchedulerPlugin<T: Component, S: LODStrategy> {
+    pub reference_rate: f32,
+    _phantom: PhantomData<(T, S)>,
+}
+
+impl<T: Component, S: LODStrategy> Default for AnimationSchedulerPlugin<T, S> {
+    fn default() -> Self {
+        Self {
+            reference_rate: 60.0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Component, S: LODStrategy> AnimationSchedulerPlugin<T, S> {
+    /// Sets the frame rate `update_rate` is converted into intervals against
+    pub fn with_reference_rate(mut self, reference_rate: f32) -> Self {
+        self.reference_rate = reference_rate;
+        self
+    }
+}
+
+impl<T: Component, S: LODStrategy> Plugin for AnimationSchedulerPlugin<T, S>
+where
+    S::ComponentData: AnimationLODSource,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AnimationScheduler::<T>::new(self.reference_rate))
+            .add_systems(
+                PostUpdate,
+                (
+                    (
+                        assign_animation_slices::<T, S>,
+                        link_time_sliced_players::<T>,
+                        advance_time_slices::<T>,
+                    ).chain().before(Animation),
+                    mask_time_sliced_players
+                        .after(advance_animations)
+                        .before(animate_targets),
+                    restore_time_sliced_players.after(animate_targets),
+                ),
+            );
+    }
+}
+
+/// `T` entities whose strategy data changed, with their slice if they have one
+type SliceAssignment<S> = (
+    Entity,
+    &'static <S as LODStrategy>::ComponentData,
+    Option<&'static mut AnimationTimeSlice>,
+);
+type ChangedLODData<T, S> = (With<T>, Changed<<S as LODStrategy>::ComponentData>);
+
+/// Buckets `T` entities whose `AnimationLODData` changed
+fn assign_animation_slices<T: Component, S: LODStrategy>(
+    mut commands: Commands,
+    mut scheduler: ResMut<AnimationScheduler<T>>,
+    mut query: Query<SliceAssignment<S>, ChangedLODData<T, S>>,
+    mut removed: RemovedComponents<T>,
+) where
+    S::ComponentData: AnimationLODSource,
+{
+    for entity in removed.read() {
+        for bucket in scheduler.frame_buckets.values_mut() {
+            bucket.retain(|e| *e != entity);
+        }
+    }
+
+    for (entity, data, slice) in query.iter_mut() {
+        let animation = data.animation_data();
+        let previous_interval = slice.as_ref()
+            .filter(|slice| !slice.frozen)
+            .map(|slice| slice.update_interval);
+
+        if !animation.animation_enabled || animation.update_rate <= 0.0 {
+            if let Some(previous) = previous_interval {
+                scheduler.remove_from_bucket(entity, previous);
+            }
+            // Frozen slices keep accumulating time so they can catch up later,
+            // otherwise disabled animations are left to the LOD strategy
+            match (slice, animation.frozen) {
+                (Some(mut slice), true) => slice.frozen = true,
+                (None, true) => {
+                    commands.entity(entity).insert(AnimationTimeSlice {
+                        frozen: true,
+                        ..default()
+                    });
+                }
+                (Some(_), false) => {
+                    commands.entity(entity).remove::<AnimationTimeSlice>();
+                }
+                (None, false) => {}
+            }
+            continue;
+        }
+
+        let interval = scheduler.interval_for(animation.update_rate);
+        if previous_interval == Some(interval) {
+            continue;
+        }
+
+        let frame_offset = scheduler.insert(entity, previous_interval, interval);
+        match slice {
+            Some(mut slice) => {
+                slice.update_interval = interval;
+                slice.frame_offset = frame_offset;
+                slice.frozen = false;
+            }
+            None => {
+                commands.entity(entity).insert(AnimationTimeSlice {
+                    update_interval: interval,
+                    frame_offset,
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
+/// Finds the `T` entity owning each new `AnimationPlayer`
+fn link_time_sliced_players<T: Component>(
+    mut commands: Commands,
+    players: Query<Entity, Added<AnimationPlayer>>,
+    parents: Query<&ChildOf>,
+    roots: Query<(), With<T>>,
+) {
+    for player in players.iter() {
+        let root = std::iter::once(player)
+            .chain(parents.iter_ancestors(player))
+            .find(|entity| roots.contains(*entity));
+
+        if let Some(root) = root {
+            commands.entity(player).insert(TimeSlicedAnimationPlayer {
+                root,
+                evaluated: false,
+                paused: Vec::new(),
+                weights: Vec::new(),
+            });
+        }
+    }
+}
+
+/// Accumulates skipped time, then pauses players that aren't due and catches
+/// up the ones that are
+fn advance_time_slices<T: Component>(
+    time: Res<Time>,
+    mut scheduler: ResMut<AnimationScheduler<T>>,
+    mut slices: Query<&mut AnimationTimeSlice, With<T>>,
+    mut players: Query<(
+        &mut AnimationPlayer,
+        &mut TimeSlicedAnimationPlayer,
+        Option<&AnimationGraphHandle>,
+    )>,
+    graphs: Res<Assets<AnimationGraph>>,
+    clips: Res<Assets<AnimationClip>>,
+) {
+    scheduler.current_frame += 1;
+    let frame = scheduler.current_frame;
+    let delta = time.delta_secs();
+
+    for mut slice in slices.iter_mut() {
+        slice.accumulated_time += delta;
+        if slice.is_due(frame) {
+            slice.step_time = slice.accumulated_time;
+            slice.accumulated_time = 0.0;
+            slice.last_update_frame = frame;
+        }
+    }
+
+    for (mut player, mut link, graph_handle) in players.iter_mut() {
+        let slice = slices.get(link.root).ok();
+        let due = slice.is_none_or(|slice| slice.last_update_frame == frame);
+
+        // A new player is evaluated once so a frozen scene doesn't show its bind pose
+        if !link.evaluated {
+            link.evaluated = true;
+            if !due {
+                continue;
+            }
+        }
+
+        if due {
+            if link.paused.is_empty() {
+                continue;
+            }
+            // `advance_animations` adds this frame's delta, seek over the rest
+            let catch_up = slice.map_or(0.0, |slice| slice.step_time - delta).max(0.0);
+            let graph = graph_handle.and_then(|handle| graphs.get(handle));
+            for node in link.paused.drain(..) {
+                let Some(animation) = player.animation_mut(node) else {
+                    continue;
+                };
+                let mut seek_time = animation.seek_time() + catch_up * animation.speed();
+                // `advance_animations` wraps overshoot, but only one clip length
+                // of undershoot when playing backwards
+                if seek_time < 0.0
+                    && let Some(duration) = graph.and_then(|graph| clip_duration(graph, &clips, node))
+                {
+                    seek_time = seek_time.rem_euclid(duration);
+                }
+                animation.resume().seek_to(seek_time);
+            }
+        } else {
+            let TimeSlicedAnimationPlayer { paused, .. } = &mut *link;
+            for (node, animation) in player.playing_animations_mut() {
+                if !animation.is_paused() {
+                    animation.pause();
+                    paused.push(*node);
+                }
+            }
+        }
+    }
+}
+
+/// Duration of the clip at `node`, if it is a loaded clip
+pub fn clip_duration(
+    graph: &AnimationGraph,
+    clips: &Assets<AnimationClip>,
+    node: AnimationNodeIndex,
+) -> Option<f32> {
+    let AnimationNodeType::Clip(ref handle) = graph.get(node)?.node_type else {
+        return None;
+    };
+    clips.get(handle)
+        .map(AnimationClip::duration)
+        .filter(|duration| *duration > 0.0)
+}
+
+/// Zeroes the weights of paused slices so `animate_targets` skips them
+fn mask_time_sliced_players(
+    mut players: Query<(&mut AnimationPlayer, &mut TimeSlicedAnimationPlayer)>,
+) {
+    for (mut player, mut link) in players.iter_mut() {
+        if link.paused.is_empty() {
+            continue;
+        }
+        let TimeSlicedAnimationPlayer { paused, weights, .. } = &mut *link;
+        for node in paused.iter() {
+            if let Some(animation) = player.animation_mut(*node) {
+                weights.push((*node, animation.weight()));
+                animation.set_weight(0.0);
+            }
+        }
+    }
+}
+
+/// Restores the weights hidden from `animate_targets`
+fn restore_time_sliced_players(
+    mut players: Query<(&mut AnimationPlayer, &mut TimeSlicedAnimationPlayer)>,
+) {
+    for (mut player, mut link) in players.iter_mut() {
+        if link.weights.is_empty() {
+            continue;
+        }
+        for (node, weight) in link.weights.drain(..) {
+            if let Some(animation) = player.animation_mut(node) {
+                animation.set_weight(weight);
+            }
+        }
+    }
+}
+
+/// Animation state carried from a replaced scene's `AnimationPlayer` to the
+/// player of the scene replacing it
+#[derive(Component)]
+pub struct AnimationHandoff {
+    pub player: AnimationPlayer,
+    pub transitions: Option<AnimationTransitions>,
+    pub graph: Option<AnimationGraphHandle>,
+    pub captured_at: f32,   // Elapsed time the captured state corresponds to
+}
+
+impl AnimationHandoff {
+    /// Captures `player`, resuming the animations the scheduler paused and
+    /// catching them up by the `skipped` time
+    pub(crate) fn capture(
+        player: &AnimationPlayer,
+        transitions: Option<&AnimationTransitions>,
+        graph: Option<&AnimationGraphHandle>,
+        link: Option<&TimeSlicedAnimationPlayer>,
+        skipped: f32,
+        captured_at: f32,
+    ) -> Self {
+        let mut player = player.clone();
+        for node in link.map(|link| link.paused.as_slice()).unwrap_or(&[]) {
+            if let Some(animation) = player.animation_mut(*node) {
+                let seek_time = animation.seek_time() + skipped * animation.speed();
+                animation.resume().seek_to(seek_time);
+            }
+        }
+
+        Self {
+            player,
+            transitions: transitions.cloned(),
+            graph: graph.cloned(),
+            captured_at,
+        }
+    }
+}
+
+/// Keeps animation time continuous when a strategy swaps the `SceneRoot` of a
+/// `T` entity, e.g. `MeshSwapLODStrategy`.
+///
+/// The old player is captured just before the scene spawner despawns it, and
+/// copied onto the new scene's player (with its transitions and graph) once it
+/// appears, caught up by however long the new scene took to spawn.
+pub struct AnimationHandoffPlugin<T: Component> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Component> Default for AnimationHandoffPlugin<T> {
+    fn default() -> Self {
+        Self { _phantom: PhantomData }
+    }
+}
+
+impl<T: Component> Plugin for AnimationHandoffPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(SpawnScene, capture_animation_handoff::<T>.before(scene_spawner))
+            .add_systems(PostUpdate, apply_animation_handoff::<T>.before(Animation));
+    }
+}
+
+/// What an `AnimationHandoff` is captured from
+type HandoffPlayer = (
+    &'static AnimationPlayer,
+    Option<&'static AnimationTransitions>,
+    Option<&'static AnimationGraphHandle>,
+    Option<&'static TimeSlicedAnimationPlayer>,
+);
+
+/// Snapshots the player of `T` entities whose scene is about to be replaced
+fn capture_animation_handoff<T: Component>(
+    mut commands: Commands,
+    time: Res<Time>,
+    swapped: Query<Entity, (With<T>, Changed<SceneRoot>)>,
+    descendants: Query<&Children>,
+    players: Query<HandoffPlayer>,
+    mut slices: Query<&mut AnimationTimeSlice>,
+) {
+    for entity in swapped.iter() {
+        // Nothing to carry over on the first spawn
+        let Some((player, transitions, graph, link)) = descendants.iter_descendants(entity)
+            .find_map(|descendant| players.get(descendant).ok())
+        else {
+            continue;
+        };
+
+        let skipped = if link.is_some_and(TimeSlicedAnimationPlayer::has_paused) {
+            slices.get_mut(entity)
+                .map(|mut slice| std::mem::take(&mut slice.accumulated_time))
+                .unwrap_or(0.0)
+        } else {
+            0.0
+        };
+
+        // The old player was last advanced on the previous frame
+        commands.entity(entity).insert(AnimationHandoff::capture(
+            player,
+            transitions,
+            graph,
+            link,
+            skipped,
+            time.elapsed_secs() - time.delta_secs(),
+        ));
+    }
+}
+
+/// Hands captured animation state to the first player of the new scene
+fn apply_animation_handoff<T: Component>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut players: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+    parents: Query<&ChildOf>,
+    handoffs: Query<&AnimationHandoff, With<T>>,
+) {
+    for (entity, mut player) in players.iter_mut() {
+        let Some((root, handoff)) = parents.iter_ancestors(entity)
+            .find_map(|ancestor| handoffs.get(ancestor).ok().map(|handoff| (ancestor, handoff)))
+        else {
+            continue;
+        };
+
+        // `advance_animations` adds this frame's delta, seek over any frames
+        // the new scene took to spawn
+        let catch_up = (time.elapsed_secs() - handoff.captured_at - time.delta_secs()).max(0.0);
+        *player = handoff.player.clone();
+        for (_, animation) in player.playing_animations_mut() {
+            if !animation.is_paused() {
+                let seek_time = animation.seek_time() + catch_up * animation.speed();
+                animation.seek_to(seek_time);
+            }
+        }
+
+        let mut new_player = commands.entity(entity);
+        if let Some(transitions) = &handoff.transitions {
+            new_player.insert(transitions.clone());
+        }
+        if let Some(graph) = &handoff.graph {
+            new_player.insert(graph.clone());
+        }
+        commands.entity(root).remove::<AnimationHandoff>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::lod_system::core::LODStrategy;
+    use crate::lod_system::strategies::{AnimationLODConfig, AnimationLODStrategy, DisabledAnimationMode};
+    use std::time::Duration;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[derive(Component)]
+    struct TestRat;
+
+    /// World with a manually advanced clock and a 60 fps `AnimationScheduler`
+    fn scheduler_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(AnimationScheduler::<TestRat>::new(60.0));
+        world.init_resource::<Assets<AnimationGraph>>();
+        world.init_resource::<Assets<AnimationClip>>();
+        world
+    }
+
+    fn spawn_animated(world: &mut World, update_rate: f32) -> Entity {
+        world.spawn((TestRat, AnimationLODData { animation_enabled: true, update_rate, frozen: false })).id()
+    }
+
+    fn assign(world: &mut World) {
+        world.run_system_once(assign_animation_slices::<TestRat, AnimationLODStrategy>).unwrap();
+    }
+
+    /// Advances the clock by `seconds` and slices one frame
+    fn step(world: &mut World, seconds: f32) {
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(advance_time_slices::<TestRat>).unwrap();
+    }
+
+    fn slice(world: &World, entity: Entity) -> AnimationTimeSlice {
+        *world.get::<AnimationTimeSlice>(entity).unwrap()
+    }
+
+    fn scheduler(world: &World) -> &AnimationScheduler<TestRat> {
+        world.resource::<AnimationScheduler<TestRat>>()
+    }
+
+    #[test]
+    fn update_rates_are_bucketed_by_interval_with_staggered_offsets() {
+        let mut world = scheduler_world();
+        let slow = [20.0, 20.0, 20.0].map(|rate| spawn_animated(&mut world, rate));
+        let full = spawn_animated(&mut world, 60.0);
+        assign(&mut world);
+
+        assert_eq!(scheduler(&world).interval_for(20.0), 3);
+        assert_eq!(scheduler(&world).bucket_len(3), 3);
+        assert_eq!(scheduler(&world).bucket_len(1), 1);
+        let mut offsets = slow.map(|rat| slice(&world, rat).frame_offset);
+        offsets.sort();
+        assert_eq!(offsets, [0, 1, 2]);
+        assert_eq!(slice(&world, full).update_interval, 1);
+
+        // A new rate moves the entity to another bucket
+        world.get_mut::<AnimationLODData>(slow[0]).unwrap().update_rate = 30.0;
+        assign(&mut world);
+        assert_eq!(scheduler(&world).bucket_len(3), 2);
+        assert_eq!(scheduler(&world).bucket_len(2), 1);
+        assert_eq!(slice(&world, slow[0]).update_interval, 2);
+
+        // Disabling the animation without freezing leaves the scheduler
+        world.get_mut::<AnimationLODData>(slow[1]).unwrap().animation_enabled = false;
+        assign(&mut world);
+        assert_eq!(scheduler(&world).bucket_len(3), 1);
+        assert!(world.get::<AnimationTimeSlice>(slow[1]).is_none());
+    }
+
+    #[test]
+    fn one_entity_of_a_bucket_is_due_each_frame() {
+        let mut world = scheduler_world();
+        let rats = [20.0, 20.0, 20.0].map(|rate| spawn_animated(&mut world, rate));
+        assign(&mut world);
+
+        for frame in 1..=9 {
+            step(&mut world, FRAME);
+            let due: Vec<_> = rats.iter()
+                .filter(|rat| slice(&world, **rat).last_update_frame == frame)
+                .collect();
+            assert_eq!(due.len(), 1, "frame {frame}");
+        }
+
+        // Past the first interval each update applies three frames of time,
+        // and whatever passed since is held for the next one
+        for rat in rats {
+            let slice = slice(&world, rat);
+            assert!((slice.step_time - 3.0 * FRAME).abs() < 1e-5);
+            let waiting = (9 - slice.last_update_frame) as f32 * FRAME;
+            assert!((slice.accumulated_time - waiting).abs() < 1e-5);
+        }
+    }
+
+    /// A 20 Hz rat owning a player of a 2 second clip at twice its speed
+    fn spawn_time_sliced_player(world: &mut World) -> (Entity, Entity, AnimationNodeIndex) {
+        let mut clip = AnimationClip::default();
+        clip.set_duration(2.0);
+        let clip = world.resource_mut::<Assets<AnimationClip>>().add(clip);
+        let (graph, node) = AnimationGraph::from_clip(clip);
+        let graph = world.resource_mut::<Assets<AnimationGraph>>().add(graph);
+
+        let rat = spawn_animated(world, 20.0);
+        let mut player = AnimationPlayer::default();
+        player.play(node).repeat().set_speed(2.0);
+        let player = world.spawn((
+            player,
+            AnimationGraphHandle(graph),
+            ChildOf(rat),
+        )).id();
+        assign(world);
+        world.run_system_once(link_time_sliced_players::<TestRat>).unwrap();
+        (rat, player, node)
+    }
+
+    fn seek_time(world: &World, player: Entity, node: AnimationNodeIndex) -> f32 {
+        world.get::<AnimationPlayer>(player).unwrap().animation(node).unwrap().seek_time()
+    }
+
+    fn is_paused(world: &World, player: Entity, node: AnimationNodeIndex) -> bool {
+        world.get::<AnimationPlayer>(player).unwrap().animation(node).unwrap().is_paused()
+    }
+
+    #[test]
+    fn players_pause_between_due_frames_and_catch_up_the_skipped_time() {
+        let mut world = scheduler_world();
+        let (rat, player, node) = spawn_time_sliced_player(&mut world);
+        assert_eq!(world.get::<TimeSlicedAnimationPlayer>(player).unwrap().root, rat);
+
+        // Run up to the rat's first due frame
+        while slice(&world, rat).last_update_frame == 0 {
+            step(&mut world, FRAME);
+        }
+        let start = seek_time(&world, player, node);
+        assert!(!is_paused(&world, player, node));
+
+        // Held between due frames, one of them a long hitch
+        step(&mut world, FRAME);
+        assert!(is_paused(&world, player, node));
+        assert!(world.get::<TimeSlicedAnimationPlayer>(player).unwrap().has_paused());
+        step(&mut world, 0.1);
+        assert!(is_paused(&world, player, node));
+        assert_eq!(seek_time(&world, player, node), start);
+
+        // The due frame seeks over the skipped frames, leaving this frame's
+        // delta to `advance_animations`
+        step(&mut world, FRAME);
+        assert!(!is_paused(&world, player, node));
+        let caught_up = seek_time(&world, player, node) - start;
+        assert!((caught_up - (FRAME + 0.1) * 2.0).abs() < 1e-5);
+        assert!(!world.get::<TimeSlicedAnimationPlayer>(player).unwrap().has_paused());
+    }
+
+    /// Applies `AnimationLODStrategy`'s transition to `to_level` and reassigns slices
+    fn transition(world: &mut World, rat: Entity, to_level: u8, disabled_mode: DisabledAnimationMode) {
+        let config = AnimationLODConfig { disabled_mode, ..default() };
+        let mut data = world.entity_mut(rat).take::<AnimationLODData>().unwrap();
+        AnimationLODStrategy::transition(&mut world.commands(), rat, 0, to_level, &config, &mut data);
+        world.flush();
+        world.entity_mut(rat).insert(data);
+        assign(world);
+    }
+
+    #[test]
+    fn frozen_animations_hold_their_seek_time_and_resume_from_it() {
+        let mut world = scheduler_world();
+        let (rat, player, node) = spawn_time_sliced_player(&mut world);
+        while slice(&world, rat).last_update_frame == 0 {
+            step(&mut world, FRAME);
+        }
+        let held = seek_time(&world, player, node);
+
+        transition(&mut world, rat, 2, DisabledAnimationMode::Freeze);
+        assert!(world.get::<AnimationLODData>(rat).unwrap().frozen);
+        assert!(slice(&world, rat).frozen);
+        assert_eq!(scheduler(&world).bucket_len(3), 0);
+
+        let mut elapsed = 0.0;
+        for _ in 0..30 {
+            step(&mut world, FRAME);
+            elapsed += FRAME;
+            assert!(is_paused(&world, player, node));
+            assert_eq!(seek_time(&world, player, node), held);
+        }
+
+        // Re-enabled at 10 Hz it resumes from the held pose, caught up to
+        // where it would have been had it kept playing
+        transition(&mut world, rat, 1, DisabledAnimationMode::Freeze);
+        assert!(!slice(&world, rat).frozen);
+        assert_eq!(slice(&world, rat).update_interval, 6);
+        while is_paused(&world, player, node) {
+            step(&mut world, FRAME);
+            elapsed += FRAME;
+        }
+        let caught_up = seek_time(&world, player, node) - held;
+        assert!((caught_up - (elapsed - FRAME) * 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn handoffs_carry_the_clip_seek_time_and_speed_to_the_new_scene() {
+        let mut world = scheduler_world();
+        let mut clip = AnimationClip::default();
+        clip.set_duration(2.0);
+        let clip = world.resource_mut::<Assets<AnimationClip>>().add(clip);
+        let (graph, nodes) = AnimationGraph::from_clips([clip.clone(), clip]);
+        let graph = world.resource_mut::<Assets<AnimationGraph>>().add(graph);
+        let node = nodes[1];
+
+        let rat = world.spawn((TestRat, SceneRoot(Handle::default()))).id();
+        let mut player = AnimationPlayer::default();
+        let mut transitions = AnimationTransitions::new();
+        transitions.play(&mut player, node, Duration::ZERO).repeat().set_speed(1.5).seek_to(0.7);
+        let old_player = world.spawn((player, transitions, AnimationGraphHandle(graph.clone()), ChildOf(rat))).id();
+
+        // The swap is captured a frame after the old player last advanced
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.9));
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.1));
+        world.run_system_once(capture_animation_handoff::<TestRat>).unwrap();
+        assert!(world.get::<AnimationHandoff>(rat).is_some());
+
+        // The new scene's player appears a frame later
+        world.despawn(old_player);
+        let new_player = world.spawn((AnimationPlayer::default(), ChildOf(rat))).id();
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.1));
+        world.run_system_once(apply_animation_handoff::<TestRat>).unwrap();
+
+        let player = world.get::<AnimationPlayer>(new_player).unwrap();
+        let animation = player.animation(node).unwrap();
+        assert!(player.animation(nodes[0]).is_none());
+        assert_eq!(animation.speed(), 1.5);
+        assert_eq!(animation.repeat_mode(), bevy::animation::RepeatAnimation::Forever);
+        // Caught up by the frame the old scene didn't play, `advance_animations` adds this one
+        assert!((animation.seek_time() - (0.7 + 0.1 * 1.5)).abs() < 1e-5);
+        assert_eq!(world.get::<AnimationGraphHandle>(new_player).unwrap().0, graph);
+        assert_eq!(world.get::<AnimationTransitions>(new_player).unwrap().get_main_animation(), Some(node));
+        assert!(world.get::<AnimationHandoff>(rat).is_none());
+    }
+}
@@ -0,0 +1,945 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::app::PluginsState;
+use bevy::audio::AudioPlugin;
+use bevy::gltf::Gltf;
+use bevy::render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use image::{ImageBuffer, LumaA, Pixel, Rgba};
+
+use crate::lod_system::vat::{
+    VATClip, VATConfig, VATEncoding, VATFile, VATNormalEncoding, VATPositionEncoding, decode_vat_texel,
+    octahedral_encode,
+};
+
+// Offline baking of skinned glTF animations into Vertex Animation Textures
+
+/// Gives up on a bake that hasn't finished after this long, e.g. because an
+/// asset never loads
+const BAKE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// What `bake_vat` samples and where it writes the textures
+#[derive(Clone, Debug)]
+pub struct VATBakeSettings {
+    pub gltf_path: String,      // Relative to the asset folder
+    pub clip_indices: Vec<usize>, // `Animation{n}` of the glTF, packed in this order
+    pub config: VATConfig,
+    pub output_dir: PathBuf,
+    pub name: String,           // e.g. "rat"
+    pub label: String,          // e.g. "med", for `rat_positions_med.png`
+}
+
+impl VATBakeSettings {
+    pub fn new(gltf_path: impl Into<String>, clip_index: usize, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            gltf_path: gltf_path.into(),
+            clip_indices: vec![clip_index],
+            config: VATConfig::default(),
+            output_dir: output_dir.into(),
+            name: "vat".to_string(),
+            label: String::new(),
+        }
+    }
+
+    pub fn with_config(mut self, config: VATConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Packs several clips into one atlas, in this order
+    pub fn with_clips(mut self, clip_indices: impl IntoIterator<Item = usize>) -> Self {
+        self.clip_indices = clip_indices.into_iter().collect();
+        self
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>, label: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.label = label.into();
+        self
+    }
+
+    /// Parses `<gltf> <clip indices> <output dir> <name> [label] [frames] [fps]`,
+    /// with the clip indices separated by commas, followed by any of
+    /// `--positions=unorm16|unorm8`, `--normals=unorm16|octahedral8` and
+    /// `--rest-delta` to pick the `VATEncoding`
+    pub fn from_args(args: &[String]) -> Result<Self, VATBakeError> {
+        let usage = || VATBakeError::Usage(
+            "bake-vat <gltf> <clip indices, e.g. 0,2,3> <output dir> <name> [label] [frames] [fps] \
+            [--positions=unorm16|unorm8] [--normals=unorm16|octahedral8] [--rest-delta]".to_string()
+        );
+        let (flags, args): (Vec<&String>, Vec<&String>) = args.iter().partition(|arg| arg.starts_with("--"));
+        let [gltf, clips, output_dir, name, rest @ ..] = args.as_slice() else {
+            return Err(usage());
+        };
+        let clips = clips.split(',')
+            .map(|clip| clip.trim().parse())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| usage())?;
+
+        let mut settings = Self::new(gltf.as_str(), 0, output_dir.as_str())
+            .with_clips(clips)
+            .with_name(name.as_str(), rest.first().copied().cloned().unwrap_or_default());
+        if let Some(frames) = rest.get(1) {
+            settings.config.total_frames = frames.parse().map_err(|_| usage())?;
+        }
+        if let Some(fps) = rest.get(2) {
+            settings.config.frames_per_second = fps.parse().map_err(|_| usage())?;
+        }
+
+        let encoding = &mut settings.config.encoding;
+        for flag in flags {
+            match flag.as_str() {
+                "--positions=unorm16" => encoding.positions = VATPositionEncoding::Unorm16,
+                "--positions=unorm8" => encoding.positions = VATPositionEncoding::Unorm8,
+                "--normals=unorm16" => encoding.normals = VATNormalEncoding::Unorm16,
+                "--normals=octahedral8" => encoding.normals = VATNormalEncoding::Octahedral8,
+                "--rest-delta" => encoding.rest_delta = true,
+                _ => return Err(usage()),
+            }
+        }
+        Ok(settings)
+    }
+
+    fn file_name(&self, kind: &str, extension: &str) -> PathBuf {
+        let name = match (kind.is_empty(), self.label.is_empty()) {
+            (true, true) => self.name.clone(),
+            (true, false) => format!("{}_{}", self.name, self.label),
+            (false, true) => format!("{}_{kind}", self.name),
+            (false, false) => format!("{}_{kind}_{}", self.name, self.label),
+        };
+        self.output_dir.join(format!("{name}.{extension}"))
+    }
+
+    pub fn positions_path(&self) -> PathBuf {
+        self.file_name("positions", "png")
+    }
+
+    pub fn normals_path(&self) -> PathBuf {
+        self.file_name("normals", "png")
+    }
+
+    /// The `.vat` file describing the textures
+    pub fn vat_path(&self) -> PathBuf {
+        self.file_name("", "vat")
+    }
+}
+
+#[derive(Debug)]
+pub enum VATBakeError {
+    Usage(String),
+    AssetLoad(String),
+    MissingAnimationPlayer,
+    MissingSkinnedMesh,
+    MissingAttribute(&'static str),
+    Timeout,
+    Io(std::io::Error),
+    Image(image::ImageError),
+    Metadata(ron::Error),
+}
+
+impl fmt::Display for VATBakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(usage) => write!(f, "usage: {usage}"),
+            Self::AssetLoad(path) => write!(f, "failed to load {path}"),
+            Self::MissingAnimationPlayer => write!(f, "the scene has no AnimationPlayer"),
+            Self::MissingSkinnedMesh => write!(f, "the scene has no skinned mesh"),
+            Self::MissingAttribute(name) => write!(f, "the skinned mesh has no {name} attribute"),
+            Self::Timeout => write!(f, "the bake didn't finish within {}s", BAKE_TIMEOUT.as_secs()),
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Image(error) => write!(f, "{error}"),
+            Self::Metadata(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for VATBakeError {}
+
+impl From<std::io::Error> for VATBakeError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<image::ImageError> for VATBakeError {
+    fn from(error: image::ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+impl From<ron::Error> for VATBakeError {
+    fn from(error: ron::Error) -> Self {
+        Self::Metadata(error)
+    }
+}
+
+/// A finished bake: the written `.vat` file and how much its encoding lost
+#[derive(Clone, Debug)]
+pub struct BakedVAT {
+    pub vat: VATFile,
+    pub report: VATEncodingReport,
+    // The same frames measured in every other encoding, to compare against
+    pub alternatives: Vec<VATEncodingReport>,
+}
+
+/// Largest and mean error over every baked vertex of every frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VATError {
+    pub max: f32,
+    pub mean: f32,
+}
+
+/// What an encoding costs and loses, measured by decoding the quantised
+/// texels like the shader and comparing them to the skinned vertices
+#[derive(Clone, Debug)]
+pub struct VATEncodingReport {
+    pub encoding: VATEncoding,
+    pub texture_bytes: u64,            // Position and normal textures, shared by every instance
+    pub position_error: VATError,      // Model space units
+    pub normal_error: Option<VATError>, // Degrees, `None` without normals
+}
+
+impl fmt::Display for VATEncodingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "positions {:?}{}, normals {:?}: {:.1} KiB, position error max {:.6} mean {:.6}",
+            self.encoding.positions,
+            if self.encoding.rest_delta { " rest delta" } else { "" },
+            self.encoding.normals,
+            self.texture_bytes as f64 / 1024.0,
+            self.position_error.max,
+            self.position_error.mean,
+        )?;
+        if let Some(normal_error) = self.normal_error {
+            write!(f, ", normal error max {:.3}° mean {:.3}°", normal_error.max, normal_error.mean)?;
+        }
+        Ok(())
+    }
+}
+
+/// Bakes animation clips of a skinned glTF into position and normal textures
+/// plus the `.vat` file describing them. Several clips are packed one after
+/// the other into an atlas, with a `VATClip` each in the clip table.
+///
+/// Runs a headless app without a GPU: each clip is posed by `bevy_animation` at
+/// each frame (`1 / frames_per_second` apart, at most `total_frames` frames
+/// covering the clip once) and the largest skinned primitive is skinned on the
+/// CPU. The frames are quantised as `VATConfig::encoding` asks, and the error
+/// that introduces is measured for it and every other encoding.
+pub fn bake_vat(settings: VATBakeSettings) -> Result<BakedVAT, VATBakeError> {
+    let mut app = headless_app(|app| {
+        app.insert_resource(VATBakeJob::new(settings))
+            .add_systems(Startup, load_bake_assets)
+            .add_systems(Update, (start_vat_bake, pick_vat_bake_target, pose_vat_frame).chain())
+            .add_systems(Last, (capture_vat_frame, exit_finished_bake).chain());
+    });
+
+    let started = Instant::now();
+    while app.should_exit().is_none() {
+        if started.elapsed() > BAKE_TIMEOUT {
+            return Err(VATBakeError::Timeout);
+        }
+        app.update();
+    }
+
+    app.world_mut()
+        .resource_mut::<VATBakeJob>()
+        .result
+        .take()
+        .unwrap_or(Err(VATBakeError::Timeout))
+}
+
+/// An app with the default plugins but no window or GPU, ready to be updated
+/// by hand. `setup` runs before the plugins are finished.
+pub(crate) fn headless_app(setup: impl FnOnce(&mut App)) -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(RenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .disable::<WinitPlugin>()
+            // CI machines have no audio device either
+            .disable::<AudioPlugin>(),
+    );
+    setup(&mut app);
+
+    while app.plugins_state() == PluginsState::Adding {
+        bevy::tasks::tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[derive(Resource)]
+struct VATBakeJob {
+    settings: VATBakeSettings,
+    gltf: Handle<Gltf>,
+    scene: Handle<Scene>,
+    clips: Vec<Handle<AnimationClip>>,
+    root: Option<Entity>,
+    player: Option<Entity>,
+    target: Option<Entity>,
+    // One per clip, in the atlas order
+    nodes: Vec<AnimationNodeIndex>,
+    clip_table: Vec<VATClip>,
+    next_frame: u32,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    result: Option<Result<BakedVAT, VATBakeError>>,
+}
+
+impl VATBakeJob {
+    fn new(settings: VATBakeSettings) -> Self {
+        Self {
+            settings,
+            gltf: Handle::default(),
+            scene: Handle::default(),
+            clips: Vec::new(),
+            root: None,
+            player: None,
+            target: None,
+            nodes: Vec::new(),
+            clip_table: Vec::new(),
+            next_frame: 0,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            result: None,
+        }
+    }
+
+    fn finish(&mut self, result: Result<BakedVAT, VATBakeError>) {
+        self.result.get_or_insert(result);
+    }
+
+    fn frame_count(&self) -> u32 {
+        self.clip_table.iter().map(|clip| clip.frame_count).sum()
+    }
+
+    /// The graph node and clip time of atlas frame `frame`
+    fn pose_at(&self, frame: u32) -> Option<(AnimationNodeIndex, f32)> {
+        self.clip_table.iter()
+            .zip(&self.nodes)
+            .find(|(clip, _)| frame < clip.start_frame + clip.frame_count)
+            .map(|(clip, node)| (*node, (frame - clip.start_frame) as f32 / self.settings.config.frames_per_second))
+    }
+}
+
+fn load_bake_assets(mut commands: Commands, mut job: ResMut<VATBakeJob>, asset_server: Res<AssetServer>) {
+    if job.settings.clip_indices.is_empty() {
+        job.finish(Err(VATBakeError::Usage("no clips to bake".to_string())));
+        return;
+    }
+    let path = job.settings.gltf_path.clone();
+    job.gltf = asset_server.load(path.clone());
+    job.scene = asset_server.load(GltfAssetLabel::Scene(0).from_asset(path.clone()));
+    job.clips = job.settings.clip_indices.iter()
+        .map(|index| asset_server.load(GltfAssetLabel::Animation(*index).from_asset(path.clone())))
+        .collect();
+
+    // At the origin so world space is the model space the VAT is stored in
+    job.root = Some(commands.spawn((SceneRoot(job.scene.clone()), Transform::IDENTITY)).id());
+}
+
+/// Once the scene has spawned, lays out the clip table and gives the player a
+/// graph of every clip
+fn start_vat_bake(
+    mut commands: Commands,
+    mut job: ResMut<VATBakeJob>,
+    gltfs: Res<Assets<Gltf>>,
+    clips: Res<Assets<AnimationClip>>,
+    mut graphs: ResMut<Assets<AnimationGraph>>,
+    mut players: Query<(Entity, &mut AnimationPlayer)>,
+    children: Query<&Children>,
+) {
+    let job = job.as_mut();
+    if job.player.is_some() || job.result.is_some() {
+        return;
+    }
+    let (Some(root), Some(gltf)) = (job.root, gltfs.get(&job.gltf)) else {
+        return;
+    };
+    let Some(durations) = job.clips.iter().map(|clip| clips.get(clip).map(AnimationClip::duration)).collect::<Option<Vec<f32>>>() else {
+        return;
+    };
+    if !children.contains(root) {
+        // Not spawned yet
+        return;
+    }
+
+    let player = players.iter_mut().find(|(entity, _)| children.iter_descendants(root).any(|descendant| descendant == *entity));
+    let Some((player_entity, mut player)) = player else {
+        job.finish(Err(VATBakeError::MissingAnimationPlayer));
+        return;
+    };
+
+    let config = &job.settings.config;
+    let mut start_frame = 0;
+    for ((clip, index), duration) in job.clips.iter().zip(&job.settings.clip_indices).zip(durations) {
+        let covering = (duration * config.frames_per_second).round() as u32;
+        let frame_count = covering.clamp(1, config.total_frames.max(1));
+        // Named after the glTF animation if it has a name
+        let name = gltf.named_animations.iter()
+            .find(|(_, handle)| *handle == clip)
+            .map_or_else(|| format!("Animation{index}"), |(name, _)| name.to_string());
+        job.clip_table.push(VATClip { name, start_frame, frame_count });
+        start_frame += frame_count;
+    }
+
+    let (graph, nodes) = AnimationGraph::from_clips(job.clips.iter().cloned());
+    commands.entity(player_entity).insert(AnimationGraphHandle(graphs.add(graph)));
+    player.stop_all();
+    job.nodes = nodes;
+    job.player = Some(player_entity);
+}
+
+/// Bakes the skinned primitive with the most vertices
+fn pick_vat_bake_target(
+    mut job: ResMut<VATBakeJob>,
+    meshes: Res<Assets<Mesh>>,
+    skinned: Query<(Entity, &Mesh3d), With<SkinnedMesh>>,
+    children: Query<&Children>,
+) {
+    let (Some(root), Some(_), None) = (job.root, job.player, job.target) else {
+        return;
+    };
+
+    let target = children.iter_descendants(root)
+        .filter_map(|entity| skinned.get(entity).ok())
+        .filter_map(|(entity, mesh)| Some((entity, meshes.get(&mesh.0)?.count_vertices())))
+        .max_by_key(|(_, vertex_count)| *vertex_count)
+        .map(|(entity, _)| entity);
+    match target {
+        Some(target) => job.target = Some(target),
+        None => job.finish(Err(VATBakeError::MissingSkinnedMesh)),
+    }
+}
+
+/// Plays only the clip of the frame captured this update, paused and seeked
+/// to the frame
+fn pose_vat_frame(job: Res<VATBakeJob>, mut players: Query<&mut AnimationPlayer>) {
+    let Some(player) = job.player else {
+        return;
+    };
+    if job.result.is_some() {
+        return;
+    }
+    let (Ok(mut player), Some((node, seek_time))) = (players.get_mut(player), job.pose_at(job.next_frame)) else {
+        return;
+    };
+    if !player.is_playing_animation(node) {
+        player.stop_all();
+        player.play(node).pause();
+    }
+    if let Some(animation) = player.animation_mut(node) {
+        animation.seek_to(seek_time);
+    }
+}
+
+/// Skins the target primitive in the pose animated and propagated this update,
+/// writing the textures after the last frame
+fn capture_vat_frame(
+    mut job: ResMut<VATBakeJob>,
+    meshes: Res<Assets<Mesh>>,
+    inverse_bindposes: Res<Assets<SkinnedMeshInverseBindposes>>,
+    targets: Query<(&Mesh3d, &SkinnedMesh)>,
+    joints: Query<&GlobalTransform>,
+) {
+    let job = job.as_mut();
+    let Some(target) = job.target else {
+        return;
+    };
+    if job.result.is_some() {
+        return;
+    }
+    let Ok((mesh_handle, skin)) = targets.get(target) else {
+        return;
+    };
+    let (Some(mesh), Some(bindposes)) = (meshes.get(&mesh_handle.0), inverse_bindposes.get(&skin.inverse_bindposes)) else {
+        return;
+    };
+
+    let joint_matrices: Vec<Mat4> = skin.joints.iter()
+        .zip(bindposes.iter())
+        .map(|(joint, bindpose)| {
+            joints.get(*joint).map_or(Mat4::IDENTITY, GlobalTransform::compute_matrix) * *bindpose
+        })
+        .collect();
+
+    if let Err(error) = skin_frame(mesh, &joint_matrices, &mut job.positions, &mut job.normals) {
+        job.finish(Err(error));
+        return;
+    }
+    job.next_frame += 1;
+    if job.next_frame < job.frame_count() {
+        return;
+    }
+
+    let label = mesh_handle.0.path()
+        .and_then(|path| path.label())
+        .map(String::from);
+    // Offsets are stored from the unskinned positions the mesh is drawn with
+    let Some(VertexAttributeValues::Float32x3(rest_positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        job.finish(Err(VATBakeError::MissingAttribute("POSITION")));
+        return;
+    };
+    let rest_positions: Vec<Vec3> = rest_positions.iter().copied().map(Vec3::from).collect();
+    let clips = std::mem::take(&mut job.clip_table);
+    let result = write_vat_textures(&job.settings, clips, &rest_positions, &job.positions, &job.normals, label);
+    job.finish(result);
+}
+
+/// Exits once the bake has a result, or its assets failed to load
+fn exit_finished_bake(mut job: ResMut<VATBakeJob>, asset_server: Res<AssetServer>, mut exit: EventWriter<AppExit>) {
+    let failed = asset_server.load_state(&job.gltf).is_failed()
+        || asset_server.load_state(&job.scene).is_failed()
+        || job.clips.iter().any(|clip| asset_server.load_state(clip).is_failed());
+    if failed {
+        let path = job.settings.gltf_path.clone();
+        job.finish(Err(VATBakeError::AssetLoad(path)));
+    }
+
+    match &job.result {
+        Some(Ok(_)) => { exit.write(AppExit::Success); }
+        Some(Err(_)) => { exit.write(AppExit::error()); }
+        None => {}
+    }
+}
+
+/// Linear blend skins every vertex of `mesh`, appending one frame
+fn skin_frame(mesh: &Mesh, joint_matrices: &[Mat4], positions: &mut Vec<Vec3>, normals: &mut Vec<Vec3>) -> Result<(), VATBakeError> {
+    let Some(VertexAttributeValues::Float32x3(mesh_positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return Err(VATBakeError::MissingAttribute("POSITION"));
+    };
+    let Some(VertexAttributeValues::Float32x3(mesh_normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else {
+        return Err(VATBakeError::MissingAttribute("NORMAL"));
+    };
+    let Some(VertexAttributeValues::Uint16x4(joint_indices)) = mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX) else {
+        return Err(VATBakeError::MissingAttribute("JOINT_INDEX"));
+    };
+    let Some(VertexAttributeValues::Float32x4(joint_weights)) = mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT) else {
+        return Err(VATBakeError::MissingAttribute("JOINT_WEIGHT"));
+    };
+
+    for (((position, normal), indices), weights) in mesh_positions.iter()
+        .zip(mesh_normals)
+        .zip(joint_indices)
+        .zip(joint_weights)
+    {
+        // Same blend as `skin_model` in bevy_pbr's skinning.wgsl
+        let skin = indices.iter()
+            .zip(weights)
+            .map(|(index, weight)| {
+                joint_matrices.get(*index as usize).copied().unwrap_or(Mat4::IDENTITY) * *weight
+            })
+            .fold(Mat4::ZERO, |sum, matrix| sum + matrix);
+
+        positions.push(skin.transform_point3(Vec3::from(*position)));
+        normals.push(skin.transform_vector3(Vec3::from(*normal)).normalize_or_zero());
+    }
+    Ok(())
+}
+
+/// Baked frames quantised to the integer texels of one `VATEncoding`
+struct QuantisedVAT {
+    bbox_min: Vec3,
+    bbox_max: Vec3,
+    positions: Vec<[u16; 3]>,
+    normals: Vec<[u16; 3]>,   // The first two with `Octahedral8`
+}
+
+/// Largest value of a texel channel, read back as 1.0
+fn unorm_max(bits16: bool) -> f32 {
+    if bits16 { u16::MAX as f32 } else { u8::MAX as f32 }
+}
+
+fn quantise(value: Vec3, bits16: bool) -> [u16; 3] {
+    let levels = (value.clamp(Vec3::ZERO, Vec3::ONE) * unorm_max(bits16)).round();
+    [levels.x as u16, levels.y as u16, levels.z as u16]
+}
+
+fn dequantise(levels: [u16; 3], bits16: bool) -> Vec3 {
+    Vec3::new(levels[0] as f32, levels[1] as f32, levels[2] as f32) / unorm_max(bits16)
+}
+
+impl QuantisedVAT {
+    /// `positions` and `normals` hold every frame back to back, `rest_positions`
+    /// one frame
+    fn new(encoding: &VATEncoding, rest_positions: &[Vec3], positions: &[Vec3], normals: &[Vec3]) -> Self {
+        let stored: Vec<Vec3> = if encoding.rest_delta {
+            positions.iter()
+                .zip(rest_positions.iter().cycle())
+                .map(|(position, rest)| *position - *rest)
+                .collect()
+        } else {
+            positions.to_vec()
+        };
+        let (bbox_min, bbox_max) = stored.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), position| (min.min(*position), max.max(*position)),
+        );
+        let extent = (bbox_max - bbox_min).max(Vec3::splat(f32::EPSILON));
+
+        let positions_16 = encoding.positions == VATPositionEncoding::Unorm16;
+        let positions = stored.iter()
+            .map(|position| quantise((*position - bbox_min) / extent, positions_16))
+            .collect();
+        let normals = normals.iter()
+            .map(|normal| match encoding.normals {
+                VATNormalEncoding::Unorm16 => quantise(*normal * 0.5 + 0.5, true),
+                VATNormalEncoding::Octahedral8 => quantise(octahedral_encode(*normal).extend(0.0), false),
+            })
+            .collect();
+        Self { bbox_min, bbox_max, positions, normals }
+    }
+
+    /// Decodes every texel like the shader and compares it to the baked vertex
+    fn report(
+        &self,
+        encoding: &VATEncoding,
+        rest_positions: &[Vec3],
+        positions: &[Vec3],
+        normals: &[Vec3],
+        texels: u64,
+    ) -> VATEncodingReport {
+        let positions_16 = encoding.positions == VATPositionEncoding::Unorm16;
+        let normals_16 = encoding.normals == VATNormalEncoding::Unorm16;
+        let mut position_error = VATError::default();
+        let mut normal_error = VATError::default();
+        for (index, position) in positions.iter().enumerate() {
+            let decoded = decode_vat_texel(
+                encoding,
+                self.bbox_min,
+                self.bbox_max,
+                rest_positions[index % rest_positions.len()],
+                dequantise(self.positions[index], positions_16),
+                self.normals.get(index).map(|levels| dequantise(*levels, normals_16)),
+            );
+            let error = decoded.position.distance(*position);
+            position_error.max = position_error.max.max(error);
+            position_error.mean += error;
+            if let Some(normal) = normals.get(index) {
+                let angle = decoded.normal.dot(*normal).clamp(-1.0, 1.0).acos().to_degrees();
+                normal_error.max = normal_error.max.max(angle);
+                normal_error.mean += angle;
+            }
+        }
+        let count = positions.len().max(1) as f32;
+        position_error.mean /= count;
+        normal_error.mean /= count;
+
+        let include_normals = !self.normals.is_empty();
+        let (position_bytes, normal_bytes) = encoding.texel_sizes();
+        let texel_bytes = position_bytes + if include_normals { normal_bytes } else { 0 };
+        VATEncodingReport {
+            encoding: *encoding,
+            texture_bytes: texels * texel_bytes as u64,
+            position_error,
+            normal_error: include_normals.then_some(normal_error),
+        }
+    }
+}
+
+/// Every combination of `VATEncoding` options
+fn all_vat_encodings() -> impl Iterator<Item = VATEncoding> {
+    [VATPositionEncoding::Unorm16, VATPositionEncoding::Unorm8].into_iter()
+        .flat_map(|positions| [VATNormalEncoding::Unorm16, VATNormalEncoding::Octahedral8].map(|normals| (positions, normals)))
+        .flat_map(|(positions, normals)| [false, true].map(|rest_delta| VATEncoding { positions, normals, rest_delta }))
+}
+
+/// Quantises the baked frames as `VATConfig::encoding` asks, writes them as
+/// PNGs with their `.vat` file and measures the error of every encoding
+fn write_vat_textures(
+    settings: &VATBakeSettings,
+    clips: Vec<VATClip>,
+    rest_positions: &[Vec3],
+    positions: &[Vec3],
+    normals: &[Vec3],
+    mesh: Option<String>,
+) -> Result<BakedVAT, VATBakeError> {
+    let mut encoding = settings.config.encoding;
+    if encoding.rest_delta && mesh.is_none() {
+        return Err(VATBakeError::Usage("--rest-delta needs the baked primitive's glTF label".to_string()));
+    }
+    let include_normals = settings.config.include_normals;
+    let normals = if include_normals { normals } else { &[] };
+    // Without a normals texture the shader reads the default one as `Unorm16`
+    if !include_normals {
+        encoding.normals = VATNormalEncoding::Unorm16;
+    }
+
+    let vertex_count = rest_positions.len() as u32;
+    let frame_count = clips.iter().map(|clip| clip.frame_count).sum::<u32>();
+    let texture_width = settings.config.texture_width.min(vertex_count).max(1);
+    let rows_per_frame = vertex_count.div_ceil(texture_width);
+    let texture_height = rows_per_frame * frame_count;
+
+    let quantised = QuantisedVAT::new(&encoding, rest_positions, positions, normals);
+    std::fs::create_dir_all(&settings.output_dir)?;
+    let layout = TexelLayout { texture_width, rows_per_frame, vertex_count };
+    let rgba16 = |[r, g, b]: [u16; 3]| Rgba([r, g, b, u16::MAX]);
+    let positions_path = settings.positions_path();
+    match encoding.positions {
+        VATPositionEncoding::Unorm16 => layout.encode(&quantised.positions, rgba16).save(positions_path)?,
+        VATPositionEncoding::Unorm8 => layout
+            .encode(&quantised.positions, |[r, g, b]| Rgba([r as u8, g as u8, b as u8, u8::MAX]))
+            .save(positions_path)?,
+    }
+    if include_normals {
+        let normals_path = settings.normals_path();
+        match encoding.normals {
+            VATNormalEncoding::Unorm16 => layout.encode(&quantised.normals, rgba16).save(normals_path)?,
+            // Read back as RG8 by the `VATAnimationLoader`
+            VATNormalEncoding::Octahedral8 => layout
+                .encode(&quantised.normals, |[x, y, _]| LumaA([x as u8, y as u8]))
+                .save(normals_path)?,
+        }
+    }
+
+    let vat = VATFile {
+        vertex_count,
+        frame_count,
+        frames_per_second: settings.config.frames_per_second,
+        texture_width,
+        rows_per_frame,
+        bbox_min: quantised.bbox_min.to_array(),
+        bbox_max: quantised.bbox_max.to_array(),
+        positions: file_name(&settings.positions_path()),
+        normals: include_normals.then(|| file_name(&settings.normals_path())),
+        mesh,
+        clips,
+        encoding,
+    };
+    let ron = ron::ser::to_string_pretty(&vat, ron::ser::PrettyConfig::default())?;
+    std::fs::write(settings.vat_path(), ron)?;
+
+    let texels = texture_width as u64 * texture_height as u64;
+    let report = quantised.report(&encoding, rest_positions, positions, normals, texels);
+    let alternatives = all_vat_encodings()
+        .filter(|alternative| *alternative != encoding)
+        .map(|alternative| {
+            QuantisedVAT::new(&alternative, rest_positions, positions, normals)
+                .report(&alternative, rest_positions, positions, normals, texels)
+        })
+        .collect();
+    Ok(BakedVAT { vat, report, alternatives })
+}
+
+/// Lays quantised texels out one frame after another, as `VATFile` describes
+#[derive(Clone, Copy)]
+struct TexelLayout {
+    texture_width: u32,
+    rows_per_frame: u32,
+    vertex_count: u32,
+}
+
+impl TexelLayout {
+    fn encode<P: Pixel>(&self, texels: &[[u16; 3]], pixel: impl Fn([u16; 3]) -> P) -> ImageBuffer<P, Vec<P::Subpixel>> {
+        let frame_count = texels.len() as u32 / self.vertex_count;
+        let mut image = ImageBuffer::new(self.texture_width, self.rows_per_frame * frame_count);
+        for (index, levels) in texels.iter().enumerate() {
+            let frame = index as u32 / self.vertex_count;
+            let vertex = index as u32 % self.vertex_count;
+            image.put_pixel(
+                vertex % self.texture_width,
+                frame * self.rows_per_frame + vertex / self.texture_width,
+                pixel(*levels),
+            );
+        }
+        image
+    }
+}
+
+/// Texture paths in a `.vat` file are relative to it
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::{Indices, PrimitiveTopology};
+    use bevy::render::render_asset::RenderAssetUsages;
+    use std::f32::consts::FRAC_PI_2;
+
+    /// Four vertices up a strip, the lower two on joint 0, the upper two on
+    /// joint 1 and one between them on both
+    fn two_bone_strip() -> Mesh {
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![
+                [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [1.0, 2.0, 0.0], [0.0, 1.0, 0.0],
+            ])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 5])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_JOINT_INDEX, VertexAttributeValues::Uint16x4(vec![
+                [0, 0, 0, 0], [0, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0], [0, 1, 0, 0],
+            ]))
+            .with_inserted_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, vec![
+                [1.0, 0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], [0.5, 0.5, 0.0, 0.0],
+            ])
+            .with_inserted_indices(Indices::U16(vec![0, 1, 2, 1, 3, 2]))
+    }
+
+    /// Joint 0 at the origin moved by `offset`, joint 1 a unit above it and
+    /// turned by `angle` about z, as skinning matrices
+    fn joint_matrices(offset: Vec3, angle: f32) -> [Mat4; 2] {
+        let joint0 = Mat4::from_translation(offset);
+        let joint1 = joint0 * Mat4::from_translation(Vec3::Y) * Mat4::from_rotation_z(angle);
+        [joint0, joint1 * Mat4::from_translation(Vec3::NEG_Y)]
+    }
+
+    fn assert_near(actual: Vec3, expected: Vec3, tolerance: f32) {
+        assert!(actual.distance(expected) < tolerance, "{actual} is not {expected}");
+    }
+
+    #[test]
+    fn skinning_blends_each_vertex_by_its_joint_weights() {
+        let mesh = two_bone_strip();
+        let (mut positions, mut normals) = (Vec::new(), Vec::new());
+        skin_frame(&mesh, &joint_matrices(Vec3::ZERO, 0.0), &mut positions, &mut normals).unwrap();
+        skin_frame(&mesh, &joint_matrices(Vec3::X, FRAC_PI_2), &mut positions, &mut normals).unwrap();
+
+        // The bind pose leaves the mesh as it is
+        let rest: Vec<Vec3> = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [1.0, 2.0, 0.0], [0.0, 1.0, 0.0]]
+            .map(Vec3::from).to_vec();
+        assert_eq!(positions[..5], rest);
+
+        // Frames are appended after each other, joint 1 turns its vertices
+        // about its own origin
+        let posed = &positions[5..];
+        assert_near(posed[0], Vec3::new(1.0, 0.0, 0.0), 1e-5);
+        assert_near(posed[1], Vec3::new(2.0, 0.0, 0.0), 1e-5);
+        assert_near(posed[2], Vec3::new(0.0, 1.0, 0.0), 1e-5);
+        assert_near(posed[3], Vec3::new(0.0, 2.0, 0.0), 1e-5);
+        assert_near(posed[4], Vec3::new(1.0, 1.0, 0.0), 1e-5);
+
+        // Turning about z leaves normals along z, and they stay normalised
+        assert_eq!(normals.len(), 10);
+        for normal in normals {
+            assert_near(normal, Vec3::Z, 1e-5);
+        }
+    }
+
+    #[test]
+    fn skinning_needs_joint_attributes() {
+        let mesh = two_bone_strip().with_removed_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT);
+        let result = skin_frame(&mesh, &joint_matrices(Vec3::ZERO, 0.0), &mut Vec::new(), &mut Vec::new());
+        assert!(matches!(result, Err(VATBakeError::MissingAttribute("JOINT_WEIGHT"))));
+    }
+
+    #[test]
+    fn quantised_positions_span_the_bounding_box() {
+        let mesh = two_bone_strip();
+        let (mut positions, mut normals) = (Vec::new(), Vec::new());
+        skin_frame(&mesh, &joint_matrices(Vec3::ZERO, 0.0), &mut positions, &mut normals).unwrap();
+        skin_frame(&mesh, &joint_matrices(Vec3::X, FRAC_PI_2), &mut positions, &mut normals).unwrap();
+        let rest = &positions[..5];
+
+        let encoding = VATEncoding::default();
+        let quantised = QuantisedVAT::new(&encoding, rest, &positions, &normals);
+        assert_near(quantised.bbox_min, Vec3::ZERO, 1e-5);
+        assert_near(quantised.bbox_max, Vec3::new(2.0, 2.0, 0.0), 1e-5);
+        assert_eq!(quantised.positions[1], [u16::MAX / 2 + 1, 0, 0]);
+        assert_eq!(quantised.positions[6], [u16::MAX, 0, 0]);
+        let report = quantised.report(&encoding, rest, &positions, &normals, 10);
+        assert!(report.position_error.max < 1e-4);
+
+        // Offsets from the rest pose span a smaller box
+        let rest_delta = VATEncoding { rest_delta: true, ..default() };
+        let quantised = QuantisedVAT::new(&rest_delta, rest, &positions, &normals);
+        assert_near(quantised.bbox_min, Vec3::new(-1.0, -1.0, 0.0), 1e-5);
+        assert_near(quantised.bbox_max, Vec3::new(1.0, 0.0, 0.0), 1e-5);
+        assert_eq!(quantised.positions[0], [u16::MAX / 2 + 1, u16::MAX, 0]);
+    }
+
+    #[test]
+    fn texels_are_laid_out_frame_after_frame_in_rows() {
+        let layout = TexelLayout { texture_width: 2, rows_per_frame: 3, vertex_count: 5 };
+        // Two frames of five vertices, each texel holding its frame and vertex
+        let texels: Vec<[u16; 3]> = (0..10).map(|index| [index / 5, index % 5, 0]).collect();
+        let image = layout.encode(&texels, |[frame, vertex, _]| Rgba([frame as u8, vertex as u8, 0, 255]));
+
+        assert_eq!(image.dimensions(), (2, 6));
+        for frame in 0..2u32 {
+            for vertex in 0..5u32 {
+                let texel = image.get_pixel(vertex % 2, frame * 3 + vertex / 2);
+                assert_eq!((texel[0] as u32, texel[1] as u32), (frame, vertex));
+            }
+        }
+        // The rest of the last row of each frame is left empty
+        assert_eq!(image.get_pixel(1, 2)[3], 0);
+    }
+
+    /// Where the vertices of `Assets/fixtures/two_bone.gltf` are at `frame`
+    /// of its `Wave` (turning joint 1 a quarter turn a second) and `Slide`
+    /// (moving joint 0 along x by 4 a second) clips, sampled at 4 fps
+    fn fixture_vertex(frame: u32, vertex: usize) -> Vec3 {
+        let joints = if frame < 4 {
+            joint_matrices(Vec3::ZERO, FRAC_PI_2 * frame as f32 / 4.0)
+        } else {
+            // Joint 1 keeps the last pose of `Wave`, only check joint 0
+            joint_matrices(Vec3::X * (frame - 4) as f32, 0.0)
+        };
+        let rest = [Vec3::ZERO, Vec3::X, Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 2.0, 0.0)];
+        let joint = if vertex < 2 { joints[0] } else { joints[1] };
+        joint.transform_point3(rest[vertex])
+    }
+
+    #[test]
+    fn baking_the_fixture_packs_every_clip_and_skins_each_frame() {
+        let output_dir = std::env::temp_dir().join(format!("hairyrat_bake_{}", std::process::id()));
+        let mut settings = VATBakeSettings::new("fixtures/two_bone.gltf", 0, &output_dir)
+            .with_clips([0, 1])
+            .with_name("strip", "test");
+        settings.config = VATConfig { texture_width: 3, frames_per_second: 4.0, total_frames: 10, ..default() };
+
+        let baked = bake_vat(settings.clone()).unwrap_or_else(|error| panic!("{error}"));
+        let vat = &baked.vat;
+        assert_eq!(vat.clips, [
+            VATClip { name: "Wave".into(), start_frame: 0, frame_count: 4 },
+            VATClip { name: "Slide".into(), start_frame: 4, frame_count: 2 },
+        ]);
+        assert_eq!((vat.vertex_count, vat.frame_count), (4, 6));
+        assert_eq!((vat.texture_width, vat.rows_per_frame), (3, 2));
+        assert_eq!(vat.mesh.as_deref(), Some("Mesh0/Primitive0"));
+        assert_eq!(vat.positions, "strip_positions_test.png");
+        vat.validate().unwrap();
+        assert!(baked.report.position_error.max < 1e-3);
+        assert_eq!(baked.alternatives.len(), 7);
+
+        // The written `.vat` file is the returned header
+        let written: VATFile = ron::from_str(&std::fs::read_to_string(settings.vat_path()).unwrap()).unwrap();
+        assert_eq!(written.clips, vat.clips);
+
+        let positions = image::open(settings.positions_path()).unwrap().into_rgba16();
+        assert_eq!(positions.dimensions(), (3, 12));
+        let (bbox_min, bbox_max) = (Vec3::from(vat.bbox_min), Vec3::from(vat.bbox_max));
+        for frame in 0..6 {
+            let vertices = if frame < 4 { 0..4 } else { 0..2 };
+            for vertex in vertices {
+                let texel = positions.get_pixel(vertex as u32 % 3, frame * 2 + vertex as u32 / 3);
+                let stored = Vec3::new(texel[0] as f32, texel[1] as f32, texel[2] as f32) / u16::MAX as f32;
+                let decoded = bbox_min + stored * (bbox_max - bbox_min);
+                assert_near(decoded, fixture_vertex(frame, vertex), 1e-3);
+            }
+        }
+        std::fs::remove_dir_all(output_dir).unwrap();
+    }
+}
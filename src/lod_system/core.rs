@@ -1,6 +1,15 @@
 use bevy::prelude::*;
+use bevy::ecs::schedule::{BoxedCondition, InternedScheduleLabel, ScheduleLabel};
+use bevy::platform::collections::HashMap;
+use bevy::platform::time::Instant;
+use bevy::render::view::RenderLayers;
+use bevy::transform::TransformSystem;
 use std::time::Duration;
+use std::any::TypeId;
+use std::cmp::Ordering;
 use std::marker::PhantomData;
+use std::sync::Arc;
+use crate::lod_system::spatial::{MovedFilter, SpatialGrid, update_spatial_grid};
 
 // Core LOD traits and components
 
@@ -31,14 +40,32 @@ pub trait LODStrategy: Send + Sync + 'static {
     fn requires_update() -> bool {
         false
     }
+    
+    /// Estimated cost of a transition, charged against the time budget of
+    /// `LODProcessingBudget`. Returning `None` measures the call to `transition`
+    /// instead, which misses any work deferred through `commands`.
+    fn transition_cost(
+        _from_level: u8,
+        _to_level: u8,
+        _config: &Self::Config,
+    ) -> Option<Duration> {
+        None
+    }
 }
 
 /// Core LOD level definition
+///
+/// `min_distance`/`max_distance` are the distances at which an entity *enters*
+/// this level. Once an entity is at this level it only *exits* when it leaves
+/// the wider `exit_min_distance`/`exit_max_distance` band, which keeps entities
+/// sitting on a boundary from flipping back and forth.
 #[derive(Component, Clone, Copy, Debug, PartialEq)]
 pub struct LODLevel {
     pub level: u8,
     pub min_distance: f32,
     pub max_distance: f32,
+    pub exit_min_distance: f32,
+    pub exit_max_distance: f32,
     pub update_frequency: f32,
 }
 
@@ -48,15 +75,55 @@ impl LODLevel {
             level,
             min_distance,
             max_distance,
+            exit_min_distance: min_distance,
+            exit_max_distance: max_distance,
             update_frequency,
         }
     }
     
+    /// Sets separate exit distances for both boundaries of this level
+    pub fn with_exit_distances(mut self, exit_min_distance: f32, exit_max_distance: f32) -> Self {
+        self.exit_min_distance = exit_min_distance.min(self.min_distance);
+        self.exit_max_distance = exit_max_distance.max(self.max_distance);
+        self
+    }
+    
+    /// Widens the exit band by `hysteresis` units on both boundaries
+    pub fn with_hysteresis(self, hysteresis: f32) -> Self {
+        let hysteresis = hysteresis.max(0.0);
+        self.with_exit_distances(self.min_distance - hysteresis, self.max_distance + hysteresis)
+    }
+    
+    /// Returns true if an entity already at this level should stay at it
+    pub fn retains(&self, distance: f32) -> bool {
+        distance >= self.exit_min_distance && distance < self.exit_max_distance
+    }
+    
     pub fn from_distance(distance: f32, levels: &[LODLevel]) -> Option<LODLevel> {
         levels.iter()
             .find(|l| distance >= l.min_distance && distance < l.max_distance)
             .copied()
     }
+    
+    /// Picks a level for `distance`, preferring `current` while the distance
+    /// stays inside its exit band
+    pub fn from_distance_with_hysteresis(
+        distance: f32,
+        current: &LODLevel,
+        levels: &[LODLevel],
+    ) -> Option<LODLevel> {
+        // Look the current level up by number so levels created with
+        // `LODLevel::new` (e.g. in `LODState::new`) pick up the configured bands
+        let current = levels.iter()
+            .find(|l| l.level == current.level)
+            .unwrap_or(current);
+        
+        if current.retains(distance) {
+            Some(*current)
+        } else {
+            Self::from_distance(distance, levels)
+        }
+    }
 }
 
 /// Component to track current LOD state
@@ -66,6 +133,8 @@ pub struct LODState {
     pub target_level: Option<LODLevel>,
     pub transition_timer: Timer,
     pub update_timer: Timer,
+    /// Seconds the current `target_level` has been waiting to be applied
+    pub pending_time: f32,
 }
 
 impl LODState {
@@ -75,14 +144,212 @@ impl LODState {
             target_level: None,
             transition_timer: Timer::from_seconds(0.1, TimerMode::Once),
             update_timer: Timer::from_seconds(initial_level.update_frequency, TimerMode::Repeating),
+            pending_time: 0.0,
         }
     }
+    
+    /// Returns true if the pending transition moves to a more detailed level
+    pub fn is_upgrade(&self) -> bool {
+        self.target_level.is_some_and(|target| target.level < self.current_level.level)
+    }
 }
 
-/// Component to store distance from camera
-#[derive(Component, Default)]
+/// Sent, and triggered on the entity for observers, whenever `LODPlugin`
+/// applies a transition
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LODTransitioned {
+    pub entity: Entity,
+    pub from: u8,
+    pub to: u8,
+    /// Type name of the `LODStrategy` that handled the transition
+    pub strategy: &'static str,
+}
+
+/// Component to store distance from camera, measured with the plugin's
+/// `LODDistanceMetric`
+#[derive(Component, Default, PartialEq)]
 pub struct LODDistance(pub f32);
 
+/// Optional bounding sphere radius used by `LODDistanceMetric::ScreenSize`
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct LODBoundingRadius(pub f32);
+
+/// How the value stored in `LODDistance` is measured
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum LODDistanceMetric {
+    /// Depth along the camera's forward axis. Entities behind the camera use
+    /// their straight-line distance instead of a mirrored depth
+    #[default]
+    ViewDepth,
+    /// Straight-line distance to the camera
+    Euclidean,
+    /// Projected diameter in pixels of the entity's `LODBoundingRadius`.
+    /// Larger values are closer, so levels are configured by pixel coverage,
+    /// e.g. level 0 from 150.0 to `f32::MAX`
+    ScreenSize {
+        default_radius: f32,
+        fallback_viewport_height: f32,
+    },
+}
+
+impl LODDistanceMetric {
+    pub fn screen_size(default_radius: f32) -> Self {
+        LODDistanceMetric::ScreenSize {
+            default_radius,
+            fallback_viewport_height: 1080.0,
+        }
+    }
+    
+    /// Measures `position` from `view`, using `radius` for screen-space metrics
+    pub fn measure(&self, view: &LODView, position: Vec3, radius: Option<f32>) -> f32 {
+        let to_entity = position - view.position;
+        
+        match *self {
+            LODDistanceMetric::ViewDepth => {
+                let depth = to_entity.dot(view.forward);
+                if depth >= 0.0 { depth } else { to_entity.length() }
+            }
+            LODDistanceMetric::Euclidean => to_entity.length(),
+            LODDistanceMetric::ScreenSize { default_radius, fallback_viewport_height } => {
+                let diameter = radius.unwrap_or(default_radius) * 2.0;
+                let viewport_height = view.viewport_height.unwrap_or(fallback_viewport_height);
+                
+                match view.projection {
+                    LODViewProjection::Perspective { fov } => {
+                        let depth = to_entity.dot(view.forward);
+                        if depth <= 0.0 {
+                            // Behind the camera, not on screen at all
+                            return 0.0;
+                        }
+                        let half_height = depth.max(f32::EPSILON) * (fov * 0.5).tan();
+                        diameter / (2.0 * half_height) * viewport_height
+                    }
+                    LODViewProjection::Orthographic { height } => {
+                        diameter / height.max(f32::EPSILON) * viewport_height
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Returns true if `a` asks for more detail than `b`
+    pub fn is_closer(&self, a: f32, b: f32) -> bool {
+        match self {
+            LODDistanceMetric::ScreenSize { .. } => a > b,
+            _ => a < b,
+        }
+    }
+    
+    /// Orders measurements closest (most detailed) first
+    pub fn compare(&self, a: f32, b: f32) -> Ordering {
+        match self {
+            LODDistanceMetric::ScreenSize { .. } => b.total_cmp(&a),
+            _ => a.total_cmp(&b),
+        }
+    }
+    
+    /// Measures `position` from every view and keeps the one asking for the
+    /// most detail
+    pub fn measure_closest(&self, views: &[LODView], position: Vec3, radius: Option<f32>) -> Option<f32> {
+        views.iter()
+            .map(|view| self.measure(view, position, radius))
+            .reduce(|closest, distance| if self.is_closer(distance, closest) { distance } else { closest })
+    }
+    
+    pub(crate) fn default_radius(&self) -> Option<f32> {
+        match self {
+            LODDistanceMetric::ScreenSize { default_radius, .. } => Some(*default_radius),
+            _ => None,
+        }
+    }
+}
+
+/// Camera data needed to measure LOD distances
+#[derive(Clone, Copy, Debug)]
+pub struct LODView {
+    pub position: Vec3,
+    pub forward: Vec3,
+    pub projection: LODViewProjection,
+    pub viewport_height: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LODViewProjection {
+    Perspective { fov: f32 },
+    Orthographic { height: f32 },
+}
+
+impl LODView {
+    pub fn new(transform: &GlobalTransform, camera: &Camera, projection: Option<&Projection>) -> Self {
+        let projection = match projection {
+            Some(Projection::Orthographic(ortho)) => LODViewProjection::Orthographic {
+                height: ortho.area.height(),
+            },
+            Some(Projection::Perspective(perspective)) => LODViewProjection::Perspective {
+                fov: perspective.fov,
+            },
+            // Custom projections don't expose a field of view, treat them as
+            // Bevy's default perspective
+            _ => LODViewProjection::Perspective {
+                fov: PerspectiveProjection::default().fov,
+            },
+        };
+        
+        Self {
+            position: transform.translation(),
+            forward: transform.forward().as_vec3(),
+            projection,
+            viewport_height: camera.physical_viewport_size().map(|size| size.y as f32),
+        }
+    }
+}
+
+/// Marker for cameras that drive LOD when using `LODCameraFilter::Marked`
+#[derive(Component, Default)]
+pub struct LODCamera;
+
+/// Which active cameras are considered when measuring LOD distances
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum LODCameraFilter {
+    /// Every active camera
+    #[default]
+    All,
+    /// Only active cameras with an `LODCamera` marker
+    Marked,
+    /// Only active cameras whose `RenderLayers` intersect these layers
+    Layers(RenderLayers),
+}
+
+impl LODCameraFilter {
+    pub fn matches(&self, is_marked: bool, layers: Option<&RenderLayers>) -> bool {
+        match self {
+            LODCameraFilter::All => true,
+            LODCameraFilter::Marked => is_marked,
+            LODCameraFilter::Layers(filter) => {
+                layers.unwrap_or(&RenderLayers::default()).intersects(filter)
+            }
+        }
+    }
+}
+
+/// Per entity type distance settings, inserted by `LODPlugin`
+#[derive(Resource)]
+pub struct LODDistanceSettings<T> {
+    pub metric: LODDistanceMetric,
+    pub camera_filter: LODCameraFilter,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> LODDistanceSettings<T> {
+    pub fn new(metric: LODDistanceMetric, camera_filter: LODCameraFilter) -> Self {
+        Self {
+            metric,
+            camera_filter,
+            _phantom: PhantomData,
+        }
+    }
+}
+
 /// Resource to define LOD levels for a specific entity type
 #[derive(Resource)]
 pub struct LODLevels<T> {
@@ -97,13 +364,70 @@ impl<T> LODLevels<T> {
             _phantom: PhantomData,
         }
     }
+    
+    /// Applies the same hysteresis to every level boundary
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        for level in self.levels.iter_mut() {
+            *level = level.with_hysteresis(hysteresis);
+        }
+        self
+    }
+    
+    /// Sets the hysteresis of a single boundary, i.e. between `levels[boundary]`
+    /// and `levels[boundary + 1]`
+    pub fn with_boundary_hysteresis(mut self, boundary: usize, hysteresis: f32) -> Self {
+        let hysteresis = hysteresis.max(0.0);
+        if let Some(near) = self.levels.get_mut(boundary) {
+            near.exit_max_distance = near.max_distance + hysteresis;
+        }
+        if let Some(far) = self.levels.get_mut(boundary + 1) {
+            far.exit_min_distance = far.min_distance - hysteresis;
+        }
+        self
+    }
 }
 
-/// Resource for LOD processing budget
+/// Resource for LOD processing budget, shared by every `LODPlugin` in the app.
+///
+/// Each `(T, S)` pair registered by a plugin gets a share of
+/// `max_operations_per_frame` proportional to its weight, so one entity type
+/// can't starve another.
+///
+/// Ready transitions are applied in priority order: anything waiting longer
+/// than `starvation_threshold` seconds first, then upgrades before downgrades,
+/// then closest first. Whatever doesn't fit is deferred to the next frame.
 #[derive(Resource)]
 pub struct LODProcessingBudget {
     pub max_operations_per_frame: usize,
     pub current_operations: usize,
+    /// Optional cap on the time spent in transitions per frame, charged with
+    /// `LODStrategy::transition_cost` or the measured time of each transition
+    pub max_time_per_frame: Option<Duration>,
+    pub current_time: Duration,
+    pub starvation_threshold: f32,
+    /// Ready transitions that didn't fit in this frame's budget
+    pub deferred_operations: usize,
+    /// Longest time any deferred transition has been waiting, in seconds
+    pub longest_deferred_wait: f32,
+    /// Totals of the previous frame, readable before this frame's transitions ran
+    pub last_frame: LODBudgetStats,
+    shares: HashMap<TypeId, LODBudgetShare>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LODBudgetStats {
+    pub operations: usize,
+    pub time: Duration,
+    pub deferred_operations: usize,
+    pub longest_deferred_wait: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LODBudgetShare {
+    weight: f32,
+    operations: usize,
+    time: Duration,
+    deferred: usize,
 }
 
 impl Default for LODProcessingBudget {
@@ -111,112 +435,583 @@ impl Default for LODProcessingBudget {
         Self {
             max_operations_per_frame: 50,
             current_operations: 0,
+            max_time_per_frame: None,
+            current_time: Duration::ZERO,
+            starvation_threshold: 1.0,
+            deferred_operations: 0,
+            longest_deferred_wait: 0.0,
+            last_frame: LODBudgetStats::default(),
+            shares: HashMap::default(),
         }
     }
 }
 
+impl LODProcessingBudget {
+    pub fn new(max_operations_per_frame: usize) -> Self {
+        Self {
+            max_operations_per_frame,
+            ..default()
+        }
+    }
+    
+    /// Caps the time spent in transitions per frame, in microseconds
+    pub fn with_time_budget_micros(mut self, micros: u64) -> Self {
+        self.max_time_per_frame = Some(Duration::from_micros(micros));
+        self
+    }
+    
+    fn key<T: 'static, S: 'static>() -> TypeId {
+        TypeId::of::<(T, S)>()
+    }
+    
+    /// Registers a `(T, S)` pair, returning false if it already was
+    pub fn register<T: 'static, S: 'static>(&mut self, weight: f32) -> bool {
+        let key = Self::key::<T, S>();
+        if self.shares.contains_key(&key) {
+            return false;
+        }
+        self.shares.insert(key, LODBudgetShare {
+            weight: weight.max(0.0),
+            operations: 0,
+            time: Duration::ZERO,
+            deferred: 0,
+        });
+        true
+    }
+    
+    pub fn set_weight<T: 'static, S: 'static>(&mut self, weight: f32) {
+        if let Some(share) = self.shares.get_mut(&Self::key::<T, S>()) {
+            share.weight = weight.max(0.0);
+        }
+    }
+    
+    /// Fraction of the budget belonging to `(T, S)`
+    fn share_fraction<T: 'static, S: 'static>(&self) -> f32 {
+        let Some(share) = self.shares.get(&Self::key::<T, S>()) else {
+            return 1.0;
+        };
+        
+        let total_weight: f32 = self.shares.values().map(|s| s.weight).sum();
+        if total_weight <= 0.0 {
+            return 0.0;
+        }
+        share.weight / total_weight
+    }
+    
+    /// Operations per frame available to `(T, S)`, at least one if it has any weight
+    pub fn allowance<T: 'static, S: 'static>(&self) -> usize {
+        let fraction = self.share_fraction::<T, S>();
+        if fraction <= 0.0 {
+            return 0;
+        }
+        
+        let allowance = self.max_operations_per_frame as f32 * fraction;
+        (allowance.round() as usize).max(1)
+    }
+    
+    /// Transition time per frame available to `(T, S)`, if time is capped
+    pub fn time_allowance<T: 'static, S: 'static>(&self) -> Option<Duration> {
+        self.max_time_per_frame
+            .map(|max_time| max_time.mul_f32(self.share_fraction::<T, S>()))
+    }
+    
+    pub fn time<T: 'static, S: 'static>(&self) -> Duration {
+        self.shares.get(&Self::key::<T, S>()).map_or(self.current_time, |s| s.time)
+    }
+    
+    pub fn operations<T: 'static, S: 'static>(&self) -> usize {
+        self.shares.get(&Self::key::<T, S>()).map_or(self.current_operations, |s| s.operations)
+    }
+    
+    /// Returns true if `(T, S)` or the whole budget has no operations left
+    pub fn is_exhausted<T: 'static, S: 'static>(&self) -> bool {
+        self.current_operations >= self.max_operations_per_frame
+            || self.operations::<T, S>() >= self.allowance::<T, S>()
+    }
+    
+    /// Returns true if a transition costing `cost` fits in what's left of the
+    /// budget. The first transition of a frame always fits so expensive ones
+    /// can't be starved forever.
+    pub fn can_afford<T: 'static, S: 'static>(&self, cost: Duration) -> bool {
+        if self.is_exhausted::<T, S>() {
+            return false;
+        }
+        
+        let (Some(max_time), Some(allowance)) = (self.max_time_per_frame, self.time_allowance::<T, S>()) else {
+            return true;
+        };
+        
+        let time = self.time::<T, S>();
+        if time.is_zero() {
+            return self.current_time < max_time;
+        }
+        time + cost <= allowance && self.current_time + cost <= max_time
+    }
+    
+    pub fn consume<T: 'static, S: 'static>(&mut self, cost: Duration) {
+        self.current_operations += 1;
+        self.current_time += cost;
+        if let Some(share) = self.shares.get_mut(&Self::key::<T, S>()) {
+            share.operations += 1;
+            share.time += cost;
+        }
+    }
+    
+    /// Ready transitions of `(T, S)` deferred this frame
+    pub fn deferred<T: 'static, S: 'static>(&self) -> usize {
+        self.shares.get(&Self::key::<T, S>()).map_or(self.deferred_operations, |s| s.deferred)
+    }
+    
+    pub fn defer<T: 'static, S: 'static>(&mut self, wait: f32) {
+        self.deferred_operations += 1;
+        self.longest_deferred_wait = self.longest_deferred_wait.max(wait);
+        if let Some(share) = self.shares.get_mut(&Self::key::<T, S>()) {
+            share.deferred += 1;
+        }
+    }
+    
+    pub fn reset(&mut self) {
+        self.last_frame = LODBudgetStats {
+            operations: self.current_operations,
+            time: self.current_time,
+            deferred_operations: self.deferred_operations,
+            longest_deferred_wait: self.longest_deferred_wait,
+        };
+        self.current_operations = 0;
+        self.current_time = Duration::ZERO;
+        self.deferred_operations = 0;
+        self.longest_deferred_wait = 0.0;
+        for share in self.shares.values_mut() {
+            share.operations = 0;
+            share.time = Duration::ZERO;
+            share.deferred = 0;
+        }
+    }
+}
+
+/// System sets used by `LODPlugin`, chained in this order. By default they
+/// run in `PostUpdate` after transform propagation, so distances are measured
+/// on world-space `GlobalTransform`s. Order your own systems against them, e.g.
+/// `.after(LODSystemSet::Transition)` to react to new levels the same frame.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LODSystemSet {
+    /// Spatial grid and camera distance updates
+    Distance,
+    /// Picks each entity's target level from its distance
+    SelectTarget,
+    /// Applies pending transitions through the strategy
+    Transition,
+    /// Per-frame strategy updates, for strategies that require them
+    UpdateRepresentation,
+}
+
+type RunConditionFactory = Arc<dyn Fn() -> BoxedCondition + Send + Sync>;
+
 /// Generic LOD plugin that can work with any strategy
 pub struct LODPlugin<T: Component, S: LODStrategy> {
+    pub metric: LODDistanceMetric,
+    pub camera_filter: LODCameraFilter,
+    pub schedule: InternedScheduleLabel,
+    pub budget_weight: f32,
+    run_conditions: Vec<RunConditionFactory>,
     _phantom: PhantomData<(T, S)>,
 }
 
 impl<T: Component, S: LODStrategy> Default for LODPlugin<T, S> {
     fn default() -> Self {
         Self {
+            metric: LODDistanceMetric::default(),
+            camera_filter: LODCameraFilter::default(),
+            schedule: PostUpdate.intern(),
+            budget_weight: 1.0,
+            run_conditions: Vec::new(),
             _phantom: PhantomData,
         }
     }
 }
 
+impl<T: Component, S: LODStrategy> LODPlugin<T, S> {
+    /// Sets how `LODDistance` is measured for `T` entities
+    pub fn with_metric(mut self, metric: LODDistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+    
+    /// Restricts which cameras drive LOD for `T` entities
+    pub fn with_camera_filter(mut self, camera_filter: LODCameraFilter) -> Self {
+        self.camera_filter = camera_filter;
+        self
+    }
+    
+    /// Runs the LOD systems in `schedule` instead of `PostUpdate`. Outside of
+    /// `PostUpdate` distances use last frame's `GlobalTransform`s
+    pub fn in_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedule = schedule.intern();
+        self
+    }
+    
+    /// Sets this plugin's share of the shared `LODProcessingBudget`, relative
+    /// to the weights of other `LODPlugin`s
+    pub fn with_budget_weight(mut self, weight: f32) -> Self {
+        self.budget_weight = weight;
+        self
+    }
+    
+    /// Only runs this plugin's systems while `condition` is true, e.g.
+    /// `.run_if(in_state(AppState::InGame))` to pause LOD while loading
+    pub fn run_if<M, C>(mut self, condition: C) -> Self
+    where
+        C: Condition<M> + Clone + Send + Sync + 'static,
+    {
+        self.run_conditions.push(Arc::new(move || {
+            Box::new(IntoSystem::into_system(condition.clone()))
+        }));
+        self
+    }
+}
+
 impl<T: Component, S: LODStrategy> Plugin for LODPlugin<T, S> {
     fn build(&self, app: &mut App) {
-        app.init_resource::<LODProcessingBudget>()
+        // Systems shared by every LOD plugin are only added once
+        if !app.is_plugin_added::<LODBudgetPlugin>() {
+            app.add_plugins(LODBudgetPlugin);
+        }
+        
+        let registered = app.world_mut()
+            .resource_mut::<LODProcessingBudget>()
+            .register::<T, S>(self.budget_weight);
+        if !registered {
+            warn!(
+                "LODPlugin<{}, {}> was added more than once, ignoring the duplicate",
+                std::any::type_name::<T>(),
+                std::any::type_name::<S>(),
+            );
+            return;
+        }
+        
+        app.add_event::<LODTransitioned>()
             .init_resource::<S::Config>()
-            .add_systems(Update, (
-                reset_lod_budget,
-                update_distance_from_camera::<T>,
-                calculate_target_lod::<T>,
-                apply_lod_transitions::<T, S>,
+            .init_resource::<SpatialGrid<T>>()
+            .insert_resource(LODDistanceSettings::<T>::new(self.metric, self.camera_filter.clone()))
+            .configure_sets(self.schedule, (
+                LODSystemSet::Distance,
+                LODSystemSet::SelectTarget,
+                LODSystemSet::Transition,
+                LODSystemSet::UpdateRepresentation,
             ).chain());
-            
+        
+        if self.schedule == PostUpdate.intern() {
+            app.configure_sets(
+                PostUpdate,
+                LODSystemSet::Distance.after(TransformSystem::TransformPropagate),
+            );
+        }
+        
+        let mut systems = (
+            (
+                update_spatial_grid::<T>,
+                update_distance_from_camera::<T>,
+            ).chain().in_set(LODSystemSet::Distance),
+            calculate_target_lod::<T>.in_set(LODSystemSet::SelectTarget),
+            apply_lod_transitions::<T, S>.in_set(LODSystemSet::Transition),
+        ).into_configs();
+        
+        for run_condition in &self.run_conditions {
+            systems.run_if_dyn(run_condition());
+        }
+        
+        app.add_systems(self.schedule, systems);
+        
         if S::requires_update() {
-            app.add_systems(Update, update_lod_representations::<T, S>);
+            let mut systems = update_lod_representations::<T, S>
+                .in_set(LODSystemSet::UpdateRepresentation);
+            
+            for run_condition in &self.run_conditions {
+                systems.run_if_dyn(run_condition());
+            }
+            
+            app.add_systems(self.schedule, systems);
         }
     }
+    
+    // A `LODProcessingBudget` inserted after this plugin replaced the one its
+    // share was registered in, so register again once every plugin is built
+    fn finish(&self, app: &mut App) {
+        app.world_mut()
+            .resource_mut::<LODProcessingBudget>()
+            .register::<T, S>(self.budget_weight);
+    }
+    
+    // Adding the same `LODPlugin<T, S>` twice is ignored rather than a panic
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+/// Shared budget and its per-frame reset, added by the first `LODPlugin`
+struct LODBudgetPlugin;
+
+impl Plugin for LODBudgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LODProcessingBudget>()
+            .add_systems(First, reset_lod_budget);
+    }
 }
 
 // System implementations
 
 fn reset_lod_budget(mut budget: ResMut<LODProcessingBudget>) {
-    budget.current_operations = 0;
+    budget.reset();
 }
 
+/// What `update_distance_from_camera` reads of each camera
+type LODCameraView = (
+    Entity,
+    Ref<'static, GlobalTransform>,
+    &'static Camera,
+    Option<Ref<'static, Projection>>,
+    Has<LODCamera>,
+    Option<&'static RenderLayers>,
+);
+type LODDistanceTarget<T> = (With<T>, Without<Camera>);
+
 fn update_distance_from_camera<T: Component>(
-    camera_query: Query<&Transform, (With<Camera>, Without<T>)>,
-    mut entity_query: Query<(&Transform, &mut LODDistance), (With<T>, Without<Camera>)>,
+    mut grid: ResMut<SpatialGrid<T>>,
+    lod_levels: Res<LODLevels<T>>,
+    settings: Res<LODDistanceSettings<T>>,
+    camera_query: Query<LODCameraView, Without<T>>,
+    moved_query: Query<Entity, MovedFilter<T>>,
+    mut distance_query: Query<(&mut LODDistance, Option<&LODBoundingRadius>), LODDistanceTarget<T>>,
+    mut last_cameras: Local<Vec<(Entity, Option<f32>)>>,
 ) {
-    if let Ok(camera_transform) = camera_query.single() {
-        for (entity_transform, mut distance) in entity_query.iter_mut() {
-            let to_entity = entity_transform.translation - camera_transform.translation;
-            let camera_forward = camera_transform.forward();
-            let view_distance = to_entity.dot(camera_forward.as_vec3());
-            distance.0 = view_distance.abs();
+    let metric = settings.metric;
+    let mut camera_changed = false;
+    let mut cameras = Vec::with_capacity(last_cameras.len());
+    let mut views = Vec::with_capacity(last_cameras.len());
+    
+    for (entity, transform, camera, projection, is_marked, layers) in camera_query.iter() {
+        if !camera.is_active || !settings.camera_filter.matches(is_marked, layers) {
+            continue;
+        }
+        
+        camera_changed |= transform.is_changed()
+            || projection.as_ref().is_some_and(|projection| projection.is_changed());
+        
+        let view = LODView::new(&transform, camera, projection.as_deref());
+        cameras.push((entity, view.viewport_height));
+        views.push(view);
+    }
+    
+    // Cameras being added, removed, toggled or resized also invalidate everything
+    if *last_cameras != cameras {
+        camera_changed = true;
+        *last_cameras = cameras;
+    }
+    
+    if views.is_empty() {
+        return;
+    }
+    let views = views.as_slice();
+    let is_far_cell = |grid: &SpatialGrid<T>, cell: IVec3| {
+        views.iter().all(|view| grid.is_far_cell(cell, view.position))
+    };
+    
+    // Entities in far cells are given the cell's shared distance, everyone
+    // else is measured at their own position with their own bounding radius
+    let mut write_distance = |entity: Entity, position: Vec3, cell_distance: Option<f32>| {
+        if let Ok((mut lod_distance, radius)) = distance_query.get_mut(entity)
+            && let Some(distance) = cell_distance
+                .or_else(|| metric.measure_closest(views, position, radius.map(|r| r.0)))
+        {
+            lod_distance.set_if_neq(LODDistance(distance));
+        }
+    };
+    
+    // Only a moving camera invalidates entities that didn't move themselves.
+    // Otherwise the only far cells to revisit are those entities entered or left.
+    let levels_changed = lod_levels.is_changed() || settings.is_changed();
+    let cells: Vec<IVec3> = if camera_changed || levels_changed {
+        grid.cells.keys().copied().collect()
+    } else {
+        grid.dirty_cells.iter().copied().filter(|cell| is_far_cell(&grid, *cell)).collect()
+    };
+    
+    for cell in cells {
+        if is_far_cell(&grid, cell) {
+            // Far away cells share one distance, and are only rewritten
+            // when that distance maps to a different level or an entity
+            // entered them
+            let center = grid.cell_center(cell);
+            let distance = metric.measure_closest(views, center, metric.default_radius())
+                .unwrap_or(f32::MAX);
+            let level = LODLevel::from_distance(distance, &lod_levels.levels)
+                .map_or(u8::MAX, |l| l.level);
+            
+            if grid.set_cell_level(cell, level) || levels_changed || grid.dirty_cells.contains(&cell) {
+                for &entity in grid.entities_in_cell(cell) {
+                    write_distance(entity, center, Some(distance));
+                }
+            }
+        } else {
+            grid.clear_cell_level(cell);
+            for &entity in grid.entities_in_cell(cell) {
+                if let Some(spatial_cell) = grid.get(entity) {
+                    write_distance(entity, spatial_cell.last_position, None);
+                }
+            }
+        }
+    }
+    
+    // Entities moving inside a far cell keep the cell's distance
+    for entity in moved_query.iter() {
+        let Some(spatial_cell) = grid.get(entity) else {
+            continue;
+        };
+        if !is_far_cell(&grid, spatial_cell.grid_position) {
+            write_distance(entity, spatial_cell.last_position, None);
         }
     }
 }
 
 fn calculate_target_lod<T: Component>(
     lod_levels: Res<LODLevels<T>>,
-    mut entity_query: Query<(&LODDistance, &mut LODState), With<T>>,
+    mut entity_query: Query<(Ref<LODDistance>, &mut LODState), With<T>>,
 ) {
     for (distance, mut lod_state) in entity_query.iter_mut() {
-        if let Some(target_level) = LODLevel::from_distance(distance.0, &lod_levels.levels) {
-            if target_level != lod_state.current_level {
+        // Distances are only written when they change, so untouched entities
+        // keep whatever level they already had
+        if !distance.is_changed() && !lod_levels.is_changed() {
+            continue;
+        }
+        
+        let target = LODLevel::from_distance_with_hysteresis(
+            distance.0,
+            &lod_state.current_level,
+            &lod_levels.levels,
+        );
+        
+        if let Some(target_level) = target {
+            if target_level.level != lod_state.current_level.level {
                 lod_state.target_level = Some(target_level);
+            } else {
+                // Back inside the current band, drop any pending transition
+                if lod_state.target_level.is_some() {
+                    lod_state.target_level = None;
+                }
+                if lod_state.current_level != target_level {
+                    lod_state.current_level = target_level;
+                }
             }
         }
     }
 }
 
+/// A transition whose timer has finished, waiting for budget
+struct ReadyTransition {
+    entity: Entity,
+    distance: f32,
+    is_upgrade: bool,
+    pending_time: f32,
+}
+
+fn transition_priority(
+    a: &ReadyTransition,
+    b: &ReadyTransition,
+    metric: &LODDistanceMetric,
+    starvation_threshold: f32,
+) -> Ordering {
+    let a_starving = a.pending_time >= starvation_threshold;
+    let b_starving = b.pending_time >= starvation_threshold;
+    
+    match (a_starving, b_starving) {
+        (true, true) => b.pending_time.total_cmp(&a.pending_time),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => b.is_upgrade.cmp(&a.is_upgrade)
+            .then_with(|| metric.compare(a.distance, b.distance))
+            .then_with(|| b.pending_time.total_cmp(&a.pending_time)),
+    }
+}
+
 fn apply_lod_transitions<T: Component, S: LODStrategy>(
     mut commands: Commands,
     time: Res<Time>,
     config: Res<S::Config>,
+    settings: Res<LODDistanceSettings<T>>,
     mut budget: ResMut<LODProcessingBudget>,
-    mut entity_query: Query<(Entity, &mut LODState, &mut S::ComponentData), With<T>>,
+    mut entity_query: Query<(Entity, &mut LODState, &LODDistance, &mut S::ComponentData), With<T>>,
+    mut ready: Local<Vec<ReadyTransition>>,
 ) {
-    for (entity, mut lod_state, mut component_data) in entity_query.iter_mut() {
-        if budget.current_operations >= budget.max_operations_per_frame {
-            break;
-        }
-        
-        // Handle transitions
-        if let Some(target_level) = lod_state.target_level {
+    ready.clear();
+    
+    // Tick every pending transition and collect the ones ready to apply
+    for (entity, mut lod_state, distance, _) in entity_query.iter_mut() {
+        if lod_state.target_level.is_some() {
             lod_state.transition_timer.tick(time.delta());
+            lod_state.pending_time += time.delta_secs();
             
             if lod_state.transition_timer.finished() {
-                let from_level = lod_state.current_level.level;
-                let to_level = target_level.level;
-                
-                S::transition(
-                    &mut commands,
+                ready.push(ReadyTransition {
                     entity,
-                    from_level,
-                    to_level,
-                    &config,
-                    &mut component_data,
-                );
-                
-                lod_state.current_level = target_level;
-                lod_state.target_level = None;
-                lod_state.transition_timer.reset();
-                lod_state.update_timer.set_duration(Duration::from_secs_f32(target_level.update_frequency));
-                
-                budget.current_operations += 1;
+                    distance: distance.0,
+                    is_upgrade: lod_state.is_upgrade(),
+                    pending_time: lod_state.pending_time,
+                });
             }
-        } else {
+        } else if lod_state.pending_time > 0.0 || lod_state.transition_timer.elapsed_secs() > 0.0 {
             lod_state.transition_timer.reset();
+            lod_state.pending_time = 0.0;
+        }
+    }
+    
+    let metric = settings.metric;
+    let starvation_threshold = budget.starvation_threshold;
+    ready.sort_unstable_by(|a, b| transition_priority(a, b, &metric, starvation_threshold));
+    
+    for transition in ready.iter() {
+        let Ok((entity, mut lod_state, _, mut component_data)) = entity_query.get_mut(transition.entity) else {
+            continue;
+        };
+        let Some(target_level) = lod_state.target_level else {
+            continue;
+        };
+        
+        let from_level = lod_state.current_level.level;
+        let to_level = target_level.level;
+        let estimated_cost = S::transition_cost(from_level, to_level, &config);
+        
+        if !budget.can_afford::<T, S>(estimated_cost.unwrap_or_default()) {
+            budget.defer::<T, S>(transition.pending_time);
+            continue;
         }
+        
+        let start = Instant::now();
+        S::transition(
+            &mut commands,
+            entity,
+            from_level,
+            to_level,
+            &config,
+            &mut component_data,
+        );
+        let cost = estimated_cost.unwrap_or_else(|| start.elapsed());
+        
+        let event = LODTransitioned {
+            entity,
+            from: from_level,
+            to: to_level,
+            strategy: std::any::type_name::<S>(),
+        };
+        commands.send_event(event);
+        commands.trigger_targets(event, entity);
+        
+        lod_state.current_level = target_level;
+        lod_state.target_level = None;
+        lod_state.transition_timer.reset();
+        lod_state.pending_time = 0.0;
+        lod_state.update_timer.set_duration(Duration::from_secs_f32(target_level.update_frequency));
+        
+        budget.consume::<T, S>(cost);
     }
 }
 
@@ -228,3 +1023,497 @@ fn update_lod_representations<T: Component, S: LODStrategy>(
         S::update(&time, entity, lod_state.current_level.level, &mut component_data);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+
+    #[derive(Component)]
+    struct TestRat;
+
+    /// Cost `LoggingStrategy` charges per transition, measured if `None`
+    #[derive(Resource, Default)]
+    struct TestConfig(Option<Duration>);
+
+    /// Transitions `LoggingStrategy` was asked for, as `(from, to)`
+    #[derive(Component, Default)]
+    struct TransitionLog(Vec<(u8, u8)>);
+
+    struct LoggingStrategy;
+
+    impl LODStrategy for LoggingStrategy {
+        type Config = TestConfig;
+        type ComponentData = TransitionLog;
+
+        fn transition(
+            _commands: &mut Commands,
+            _entity: Entity,
+            from_level: u8,
+            to_level: u8,
+            _config: &TestConfig,
+            component_data: &mut TransitionLog,
+        ) {
+            component_data.0.push((from_level, to_level));
+        }
+
+        fn update(_time: &Time, _entity: Entity, _current_level: u8, _component_data: &mut TransitionLog) {}
+
+        fn transition_cost(_from_level: u8, _to_level: u8, config: &TestConfig) -> Option<Duration> {
+            config.0
+        }
+    }
+
+    /// Level 0 below 10 units and level 1 from 10 to 30
+    fn test_levels() -> Vec<LODLevel> {
+        vec![
+            LODLevel::new(0, 0.0, 10.0, 0.0),
+            LODLevel::new(1, 10.0, 30.0, 0.0),
+        ]
+    }
+
+    /// Headless app stepping 50ms a frame, so a transition is applied on the
+    /// third frame its target is held. Levels exit 2 units past their bounds.
+    fn lod_app(plugin: LODPlugin<TestRat, LoggingStrategy>) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .insert_resource(LODLevels::<TestRat>::new(test_levels()).with_hysteresis(2.0))
+            .add_plugins(plugin);
+        app
+    }
+
+    fn spawn_rat(app: &mut App, position: Vec3) -> Entity {
+        app.world_mut().spawn((
+            TestRat,
+            Transform::from_translation(position),
+            LODDistance::default(),
+            LODState::new(test_levels()[0]),
+            TransitionLog::default(),
+        )).id()
+    }
+
+    /// Moves `entity` to `distance` units in front of a camera at the origin
+    /// and steps `frames` frames
+    fn hold_distance(app: &mut App, entity: Entity, distance: f32, frames: usize) {
+        app.world_mut().get_mut::<Transform>(entity).unwrap().translation = Vec3::new(0.0, 0.0, -distance);
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    fn transitions(app: &App, entity: Entity) -> &[(u8, u8)] {
+        &app.world().get::<TransitionLog>(entity).unwrap().0
+    }
+
+    #[test]
+    fn hysteresis_keeps_the_current_level_inside_the_exit_band() {
+        let levels = LODLevels::<TestRat>::new(test_levels()).with_hysteresis(2.0);
+        let near = levels.levels[0];
+        let far = levels.levels[1];
+
+        for distance in [9.0, 10.5, 11.9] {
+            assert_eq!(LODLevel::from_distance_with_hysteresis(distance, &near, &levels.levels), Some(near));
+        }
+        assert_eq!(LODLevel::from_distance_with_hysteresis(12.0, &near, &levels.levels), Some(far));
+        for distance in [11.0, 9.5, 8.0] {
+            assert_eq!(LODLevel::from_distance_with_hysteresis(distance, &far, &levels.levels), Some(far));
+        }
+        assert_eq!(LODLevel::from_distance_with_hysteresis(7.9, &far, &levels.levels), Some(near));
+    }
+
+    #[test]
+    fn oscillating_inside_the_band_never_transitions() {
+        let mut app = lod_app(LODPlugin::default());
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        app.update();
+
+        // Straddling the level 0/1 boundary at 10 without leaving 8..12
+        for _ in 0..10 {
+            hold_distance(&mut app, rat, 9.0, 3);
+            hold_distance(&mut app, rat, 11.5, 3);
+        }
+        assert!(transitions(&app, rat).is_empty());
+
+        // Crossing the exit distance transitions once, after which the same
+        // oscillation is held at level 1
+        hold_distance(&mut app, rat, 13.0, 3);
+        assert_eq!(transitions(&app, rat), [(0, 1)]);
+        for _ in 0..10 {
+            hold_distance(&mut app, rat, 11.0, 3);
+            hold_distance(&mut app, rat, 8.5, 3);
+        }
+        assert_eq!(transitions(&app, rat), [(0, 1)]);
+
+        hold_distance(&mut app, rat, 7.0, 3);
+        assert_eq!(transitions(&app, rat), [(0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn oscillating_across_the_band_every_frame_never_transitions() {
+        let mut app = lod_app(LODPlugin::default());
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        app.update();
+
+        // Leaving the band for a single frame at a time never holds the new
+        // target for the transition delay
+        for _ in 0..20 {
+            hold_distance(&mut app, rat, 13.0, 1);
+            hold_distance(&mut app, rat, 9.0, 1);
+        }
+        assert!(transitions(&app, rat).is_empty());
+    }
+
+    fn distance(app: &App, entity: Entity) -> f32 {
+        app.world().get::<LODDistance>(entity).unwrap().0
+    }
+
+    #[test]
+    fn entering_a_far_cell_takes_the_cell_distance_while_the_camera_is_still() {
+        let mut app = lod_app(LODPlugin::default());
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let resident = spawn_rat(&mut app, Vec3::new(1.0, 1.0, -101.0));
+        let rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        app.update();
+        app.update();
+        assert_eq!(distance(&app, rat), 5.0);
+        let cell_distance = distance(&app, resident);
+        assert_ne!(cell_distance, 101.0);
+
+        // Only the cell the rat entered is revisited, giving it the shared distance
+        hold_distance(&mut app, rat, 103.0, 1);
+        assert_eq!(distance(&app, rat), cell_distance);
+        assert_eq!(app.world().resource::<SpatialGrid<TestRat>>().dirty_cells.len(), 2);
+
+        // Moving inside the far cell keeps it
+        hold_distance(&mut app, rat, 101.5, 1);
+        assert_eq!(distance(&app, rat), cell_distance);
+
+        // Coming back near measures the rat's own position again
+        hold_distance(&mut app, rat, 6.0, 1);
+        assert_eq!(distance(&app, rat), 6.0);
+        assert_eq!(distance(&app, resident), cell_distance);
+    }
+
+    #[test]
+    fn view_depth_is_the_default_metric() {
+        assert_eq!(LODDistanceMetric::default(), LODDistanceMetric::ViewDepth);
+        assert_eq!(LODPlugin::<TestRat, LoggingStrategy>::default().metric, LODDistanceMetric::ViewDepth);
+    }
+
+    #[test]
+    fn far_cells_share_one_distance_regardless_of_bounding_radius() {
+        let mut app = lod_app(LODPlugin::default().with_metric(LODDistanceMetric::screen_size(0.5)));
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let small = spawn_rat(&mut app, Vec3::new(1.0, 1.0, -101.0));
+        let large = spawn_rat(&mut app, Vec3::new(2.0, 2.0, -102.0));
+        app.world_mut().entity_mut(large).insert(LODBoundingRadius(3.0));
+        app.update();
+        app.update();
+
+        // The cell's level was picked with the default radius, so that is
+        // the distance every entity in it gets
+        assert_eq!(distance(&app, small), distance(&app, large));
+
+        // Near entities are measured with their own radius
+        hold_distance(&mut app, small, 10.0, 1);
+        hold_distance(&mut app, large, 10.0, 1);
+        assert!((distance(&app, large) - distance(&app, small) * 6.0).abs() < 1e-3);
+    }
+
+    /// Two cameras 20 units apart on the x axis, the second marked with
+    /// `LODCamera` and on render layer 1, and a rat 2 units from each
+    fn two_camera_app(camera_filter: LODCameraFilter) -> (App, Entity, Entity) {
+        let mut app = lod_app(LODPlugin::default()
+            .with_metric(LODDistanceMetric::Euclidean)
+            .with_camera_filter(camera_filter));
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        app.world_mut().spawn((
+            Camera { order: 1, ..default() },
+            Transform::from_xyz(20.0, 0.0, 0.0),
+            LODCamera,
+            RenderLayers::layer(1),
+        ));
+        let near_first = spawn_rat(&mut app, Vec3::new(2.0, 0.0, 0.0));
+        let near_second = spawn_rat(&mut app, Vec3::new(18.0, 0.0, 0.0));
+        app.update();
+        (app, near_first, near_second)
+    }
+
+    #[test]
+    fn each_entity_uses_the_nearest_camera() {
+        let (app, near_first, near_second) = two_camera_app(LODCameraFilter::All);
+        assert_eq!(distance(&app, near_first), 2.0);
+        assert_eq!(distance(&app, near_second), 2.0);
+    }
+
+    #[test]
+    fn cameras_rejected_by_the_filter_are_ignored() {
+        let (app, near_first, near_second) = two_camera_app(LODCameraFilter::Marked);
+        assert_eq!(distance(&app, near_first), 18.0);
+        assert_eq!(distance(&app, near_second), 2.0);
+
+        let (app, near_first, near_second) = two_camera_app(LODCameraFilter::Layers(RenderLayers::layer(0)));
+        assert_eq!(distance(&app, near_first), 2.0);
+        assert_eq!(distance(&app, near_second), 18.0);
+    }
+
+    #[test]
+    fn deactivating_a_camera_falls_back_to_the_next_nearest() {
+        let (mut app, near_first, near_second) = two_camera_app(LODCameraFilter::All);
+        let mut cameras = app.world_mut().query::<(&mut Camera, Has<LODCamera>)>();
+        for (mut camera, is_marked) in cameras.iter_mut(app.world_mut()) {
+            camera.is_active = !is_marked;
+        }
+        app.update();
+
+        assert_eq!(distance(&app, near_first), 2.0);
+        assert_eq!(distance(&app, near_second), 18.0);
+    }
+
+    #[test]
+    fn distance_uses_the_world_position_under_a_translated_parent() {
+        let mut app = lod_app(LODPlugin::default());
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let parent = app.world_mut().spawn(Transform::from_xyz(0.0, 0.0, -20.0)).id();
+        let rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        app.world_mut().entity_mut(rat).insert(ChildOf(parent));
+        app.update();
+        assert_eq!(distance(&app, rat), 25.0);
+
+        // Moving only the parent moves the child in world space
+        app.world_mut().get_mut::<Transform>(parent).unwrap().translation.z = -2.0;
+        app.update();
+        assert_eq!(distance(&app, rat), 7.0);
+    }
+
+    #[derive(Resource)]
+    struct LODEnabled(bool);
+
+    #[test]
+    fn a_false_run_condition_stops_distances_and_transitions() {
+        let mut app = lod_app(LODPlugin::default().run_if(|enabled: Res<LODEnabled>| enabled.0));
+        app.insert_resource(LODEnabled(false));
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+
+        hold_distance(&mut app, rat, 20.0, 5);
+        assert_eq!(distance(&app, rat), 0.0);
+        assert!(transitions(&app, rat).is_empty());
+
+        app.insert_resource(LODEnabled(true));
+        hold_distance(&mut app, rat, 20.0, 3);
+        assert_eq!(distance(&app, rat), 20.0);
+        assert_eq!(transitions(&app, rat), [(0, 1)]);
+    }
+
+    fn budget(app: &App) -> &LODProcessingBudget {
+        app.world().resource::<LODProcessingBudget>()
+    }
+
+    #[test]
+    fn a_small_budget_applies_starving_then_upgrades_then_closest_first() {
+        let mut app = lod_app(LODPlugin::default());
+        {
+            let mut budget = app.world_mut().resource_mut::<LODProcessingBudget>();
+            budget.max_operations_per_frame = 0;
+            budget.starvation_threshold = 0.5;
+        }
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+
+        // The farthest downgrade waits past the starvation threshold
+        let starving = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -28.0));
+        for _ in 0..15 {
+            app.update();
+        }
+        assert_eq!(budget(&app).deferred_operations, 1);
+        assert!(budget(&app).longest_deferred_wait >= 0.5);
+
+        let far = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -25.0));
+        let near = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -15.0));
+        let upgrade = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        app.world_mut().entity_mut(upgrade).insert(LODState::new(test_levels()[1]));
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(budget(&app).deferred_operations, 4);
+
+        // One transition a frame, the rest deferred with their wait recorded
+        app.world_mut().resource_mut::<LODProcessingBudget>().max_operations_per_frame = 1;
+        app.update();
+        assert_eq!(transitions(&app, starving), [(0, 1)]);
+        assert_eq!(budget(&app).deferred_operations, 3);
+        assert!((0.1..0.5).contains(&budget(&app).longest_deferred_wait));
+
+        let expected = [(upgrade, (1, 0)), (near, (0, 1)), (far, (0, 1))];
+        for (index, (rat, transition)) in expected.into_iter().enumerate() {
+            app.update();
+            assert_eq!(transitions(&app, rat), [transition]);
+            assert_eq!(budget(&app).deferred_operations, expected.len() - index - 1);
+            for (waiting, _) in &expected[index + 1..] {
+                assert!(transitions(&app, *waiting).is_empty());
+            }
+        }
+        assert_eq!(budget(&app).longest_deferred_wait, 0.0);
+
+        app.update();
+        assert_eq!(budget(&app).last_frame.operations, 1);
+        assert_eq!(budget(&app).last_frame.deferred_operations, 0);
+    }
+
+    #[test]
+    fn the_time_cap_defers_once_spent_but_the_first_transition_always_fits() {
+        let micros = Duration::from_micros;
+        let mut budget = LODProcessingBudget::new(10).with_time_budget_micros(100);
+        assert_eq!(budget.max_time_per_frame, Some(micros(100)));
+
+        // Over the whole cap on its own, but nothing was spent yet
+        assert!(budget.can_afford::<TestRat, LoggingStrategy>(micros(250)));
+        budget.consume::<TestRat, LoggingStrategy>(micros(250));
+        assert!(!budget.can_afford::<TestRat, LoggingStrategy>(micros(1)));
+
+        budget.reset();
+        assert_eq!(budget.last_frame.time, micros(250));
+        budget.consume::<TestRat, LoggingStrategy>(micros(60));
+        assert!(budget.can_afford::<TestRat, LoggingStrategy>(micros(40)));
+        assert!(!budget.can_afford::<TestRat, LoggingStrategy>(micros(41)));
+    }
+
+    #[test]
+    fn each_registered_pair_fits_its_first_transition_into_its_share_of_time() {
+        let micros = Duration::from_micros;
+        let mut budget = LODProcessingBudget::new(10).with_time_budget_micros(100);
+        budget.register::<TestRat, LoggingStrategy>(1.0);
+        budget.register::<Camera, LoggingStrategy>(1.0);
+        assert_eq!(budget.time_allowance::<TestRat, LoggingStrategy>(), Some(micros(50)));
+
+        // The other pair overran its own share, this one still gets a transition
+        budget.consume::<Camera, LoggingStrategy>(micros(80));
+        assert!(!budget.can_afford::<Camera, LoggingStrategy>(micros(1)));
+        assert!(budget.can_afford::<TestRat, LoggingStrategy>(micros(30)));
+        budget.consume::<TestRat, LoggingStrategy>(micros(30));
+
+        // Further ones must fit both the share and what's left of the cap
+        assert!(!budget.can_afford::<TestRat, LoggingStrategy>(micros(15)));
+
+        // Once the whole cap is spent not even a first transition fits
+        budget.reset();
+        budget.consume::<Camera, LoggingStrategy>(micros(100));
+        assert!(!budget.can_afford::<TestRat, LoggingStrategy>(micros(1)));
+    }
+
+    /// Spawns `count` rats held past the level 0 exit distance until ready
+    /// and returns how many transition on each of the following frames
+    fn transitions_per_frame(app: &mut App, count: usize, frames: usize) -> Vec<usize> {
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let rats: Vec<_> = (0..count)
+            .map(|i| spawn_rat(app, Vec3::new(i as f32 * 0.1, 0.0, -20.0)))
+            .collect();
+        for _ in 0..3 {
+            app.update();
+        }
+
+        let mut applied = 0;
+        (0..frames).map(|_| {
+            app.update();
+            let total = rats.iter().filter(|rat| !transitions(app, **rat).is_empty()).count();
+            total - std::mem::replace(&mut applied, total)
+        }).collect()
+    }
+
+    #[test]
+    fn the_time_cap_limits_transitions_per_frame_by_their_cost() {
+        let time_capped_app = |cost_micros| {
+            let mut app = lod_app(LODPlugin::default());
+            app.insert_resource(TestConfig(Some(Duration::from_micros(cost_micros))));
+            app.world_mut().resource_mut::<LODProcessingBudget>().max_time_per_frame = Some(Duration::from_micros(100));
+            app
+        };
+
+        let mut app = time_capped_app(40);
+        assert_eq!(transitions_per_frame(&mut app, 5, 4), [2, 2, 1, 0]);
+        assert_eq!(budget(&app).last_frame.time, Duration::from_micros(40));
+
+        // Costing more than the whole cap still applies one a frame
+        let mut app = time_capped_app(250);
+        assert_eq!(transitions_per_frame(&mut app, 3, 4), [1, 1, 1, 0]);
+    }
+
+    /// `LODTransitioned` triggers seen by an entity observer, with their target
+    #[derive(Resource, Default)]
+    struct Observed(Vec<(Entity, LODTransitioned)>);
+
+    #[test]
+    fn transitions_are_sent_and_triggered_on_the_entity() {
+        let mut app = lod_app(LODPlugin::default());
+        app.init_resource::<Observed>();
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let observed_rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        let other_rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -5.0));
+        app.world_mut().entity_mut(observed_rat).observe(
+            |trigger: Trigger<LODTransitioned>, mut observed: ResMut<Observed>| {
+                observed.0.push((trigger.target(), *trigger.event()));
+            },
+        );
+        app.update();
+
+        app.world_mut().get_mut::<Transform>(other_rat).unwrap().translation.z = -20.0;
+        hold_distance(&mut app, observed_rat, 20.0, 3);
+
+        let strategy = std::any::type_name::<LoggingStrategy>();
+        let events = app.world().resource::<Events<LODTransitioned>>();
+        let mut sent: Vec<_> = events.get_cursor().read(events)
+            .map(|event| (event.entity, event.from, event.to, event.strategy))
+            .collect();
+        sent.sort_by_key(|(entity, ..)| *entity);
+        assert_eq!(sent, [(observed_rat, 0, 1, strategy), (other_rat, 0, 1, strategy)]);
+
+        // Only the observed entity's own transition reaches its observer
+        let observed = &app.world().resource::<Observed>().0;
+        assert_eq!(observed.len(), 1);
+        let (target, event) = observed[0];
+        assert_eq!(target, observed_rat);
+        assert_eq!((event.entity, event.from, event.to, event.strategy), (observed_rat, 0, 1, strategy));
+
+        hold_distance(&mut app, observed_rat, 5.0, 3);
+        let observed = &app.world().resource::<Observed>().0;
+        assert_eq!(observed.len(), 2);
+        assert_eq!((observed[1].1.from, observed[1].1.to), (1, 0));
+    }
+
+    /// `LODSystemSet`s in the order their probe systems ran
+    #[derive(Resource, Default)]
+    struct SetOrder(Vec<LODSystemSet>);
+
+    fn probe(set: LODSystemSet) -> impl FnMut(ResMut<SetOrder>) {
+        move |mut order: ResMut<SetOrder>| order.0.push(set)
+    }
+
+    #[test]
+    fn a_custom_schedule_still_chains_the_sets() {
+        let mut app = lod_app(LODPlugin::default().in_schedule(Update));
+        app.init_resource::<SetOrder>();
+        // Added in reverse so only the set chain can put them in order
+        for set in [LODSystemSet::Transition, LODSystemSet::SelectTarget, LODSystemSet::Distance] {
+            app.add_systems(Update, probe(set).in_set(set));
+        }
+        app.world_mut().spawn((Camera::default(), Transform::IDENTITY));
+        let rat = spawn_rat(&mut app, Vec3::new(0.0, 0.0, -20.0));
+
+        app.update();
+        assert_eq!(
+            app.world().resource::<SetOrder>().0,
+            [LODSystemSet::Distance, LODSystemSet::SelectTarget, LODSystemSet::Transition],
+        );
+
+        // Distances come from last frame's `GlobalTransform` in `Update`, the
+        // rat still transitions once it has held its target
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(distance(&app, rat), 20.0);
+        assert_eq!(transitions(&app, rat), [(0, 1)]);
+    }
+}
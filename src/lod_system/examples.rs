@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use crate::lod_system::core::*;
 use crate::lod_system::strategies::*;
 use crate::lod_system::vat::*;
+use crate::lod_system::vat_instancing::*;
+use bevy::render::storage::ShaderStorageBuffer;
 
 /// Example of how to use the new generic LOD system with rats
 /// This shows how to set up different LOD strategies
@@ -45,70 +47,101 @@ pub fn setup_animation_lod(
 /// Example setup for VAT-based LOD
 pub fn setup_vat_lod(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<VATMaterial>>,
-    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<VATInstancedMaterial>>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
     asset_server: Res<AssetServer>,
 ) {
-    // Load VAT textures (these would be pre-generated from your rat animation)
-    let position_texture_high = asset_server.load("vat/rat_positions_high.exr");
-    let normal_texture_high = asset_server.load("vat/rat_normals_high.exr");
-    let position_texture_low = asset_server.load("vat/rat_positions_low.exr");
-    let normal_texture_low = asset_server.load("vat/rat_normals_low.exr");
+    // Load baked VATs (see `bake_vat`), each `.vat` file describes its textures
+    let rat_vat_high = asset_server.load("vat/rat_high.vat");
+    let rat_vat_low = asset_server.load("vat/rat_low.vat");
     let base_color = asset_server.load("blackrat_free_glb/blackrat_color.png");
     
-    // Create VAT materials for different LOD levels
-    let vat_material_high = materials.add(create_vat_material(
-        position_texture_high.clone(),
-        normal_texture_high.clone(),
+    // Create a VAT material for each LOD level, frame counts, vertex counts
+    // and bounding boxes come from the `.vat` files. Every rat at a level
+    // shares its material and is drawn instanced.
+    let vat_material_high = materials.add(VATInstancedMaterial::from_animation(
+        rat_vat_high,
         Some(base_color.clone()),
-        60,    // 60 frames
-        5000,  // vertex count
-        2048,  // texture width
-        Vec3::new(-1.0, -1.0, -1.0),
-        Vec3::new(1.0, 1.0, 1.0),
+        &mut buffers,
     ));
-    
-    let vat_material_low = materials.add(create_vat_material(
-        position_texture_low.clone(),
-        normal_texture_low.clone(),
-        Some(base_color.clone()),
-        30,    // 30 frames for lower LOD
-        2500,  // reduced vertex count
-        1024,  // smaller texture
-        Vec3::new(-1.0, -1.0, -1.0),
-        Vec3::new(1.0, 1.0, 1.0),
+    let vat_material_low = materials.add(VATInstancedMaterial::from_animation(
+        rat_vat_low,
+        Some(base_color),
+        &mut buffers,
     ));
     
+    // The skinned primitive the VATs were baked from
+    let rat_mesh: Handle<Mesh> = asset_server.load("blackrat_furless/rat_without_fur.glb#Mesh0/Primitive0");
+    
     // Configure VAT LOD
-    let mut vat_config = VATLODConfig::default();
-    vat_config.texture_handles = vec![
-        position_texture_high,
-        position_texture_low.clone(),
-        position_texture_low, // Reuse low for very far
-    ];
-    vat_config.frame_counts = vec![60, 30, 1]; // Static frame for furthest
-    vat_config.playback_speeds = vec![1.0, 0.5, 0.0];
+    commands.insert_resource(VATLODConfig {
+        meshes: vec![rat_mesh.clone()],
+        materials: vec![
+            vat_material_high.clone(),
+            vat_material_low.clone(),
+            vat_material_low, // Reuse low for very far
+        ],
+        frame_counts: vec![60, 30, 1], // Static frame for furthest
+        playback_speeds: vec![1.0, 0.5, 0.0],
+        fade_duration: 0.3,
+        ..default()
+    });
     
-    commands.insert_resource(vat_config);
+    // Spawn VAT rats, the VATLODStrategy swaps their VAT as their level changes
+    for x in 0..10 {
+        for y in 0..10 {
+            let rat = commands.spawn((
+                Transform::from_xyz(x as f32 * 2.0, 0.0, y as f32 * 2.0),
+                Visibility::default(),
+                Rat,
+                LODDistance::default(),
+                LODState::new(LODLevel::new(0, 0.0, 10.0, 1.0 / 60.0)),
+                // Out of step, so the crowd doesn't animate in lockstep
+                VATLODData {
+                    current_frame: (x * 10 + y) as f32 * 0.37,
+                    ..default()
+                },
+            )).id();
+            
+            // Show the initial level's VAT, later ones are swapped in by transitions
+            commands.queue(SwapVATRepresentation {
+                entity: rat,
+                mesh: rat_mesh.clone(),
+                material: vat_material_high.clone(),
+                clip_index: 0,
+                fade_duration: 0.0, // Nothing to fade from yet
+            });
+        }
+    }
+}
+
+/// Example setup for an instanced VAT crowd, without LOD
+pub fn setup_vat_crowd(
+    mut commands: Commands,
+    mut instanced_materials: ResMut<Assets<VATInstancedMaterial>>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    asset_server: Res<AssetServer>,
+) {
+    let base_color = asset_server.load("blackrat_free_glb/blackrat_color.png");
+    let rat_mesh: Handle<Mesh> = asset_server.load("blackrat_furless/rat_without_fur.glb#Mesh0/Primitive0");
     
-    // Load base mesh
-    let rat_mesh = asset_server.load("blackrat_furless/rat_without_fur.glb#Mesh0");
+    // One instanced material for the whole crowd, drawn in a single instanced
+    // draw call with every rat at its own frame
+    let vat_material_crowd = instanced_materials.add(VATInstancedMaterial::from_animation(
+        asset_server.load("vat/rat_high.vat"),
+        Some(base_color),
+        &mut buffers,
+    ));
     
-    // Spawn VAT rats
     for x in 0..10 {
         for y in 0..10 {
             commands.spawn((
-                VATBundle {
-                    mesh_handle: MeshMaterial3d(vat_material_high.clone()),
-                    transform: Transform::from_xyz(x as f32 * 2.0, 0.0, y as f32 * 2.0),
-                    ..default()
-                },
+                MeshMaterial3d(vat_material_crowd.clone()),
+                Transform::from_xyz(x as f32 * 2.0, 0.0, y as f32 * 2.0),
+                // Out of step, so the crowd doesn't animate in lockstep
+                VATAnimationState::default().with_frame((x * 10 + y) as f32 * 0.37),
                 Mesh3d(rat_mesh.clone()),
                 Rat,
-                LODDistance::default(),
-                LODState::new(LODLevel::new(0, 0.0, 10.0, 1.0 / 60.0)),
-                VATLODData::default(),
             ));
         }
     }
@@ -119,6 +152,8 @@ pub fn setup_hybrid_lod(
     mut commands: Commands,
     rat_models: Res<RatModels>,
     animations: Res<Animations>,
+    mut materials: ResMut<Assets<VATInstancedMaterial>>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
     asset_server: Res<AssetServer>,
 ) {
     // Configure hybrid LOD
@@ -130,15 +165,22 @@ pub fn setup_hybrid_lod(
         high_quality_distance: 10.0,
         medium_quality_distance: 20.0,
         low_quality_distance: 30.0,
+        ..default()
     };
     
-    // VAT config for medium distances
-    hybrid_config.vat_config.texture_handles = vec![
-        asset_server.load("vat/rat_positions_med.exr"),
-        asset_server.load("vat/rat_positions_low.exr"),
+    // VAT config for medium distances, indexed by LOD level like the other
+    // configs (only level 2 uses it)
+    let rat_vat = materials.add(VATInstancedMaterial::from_animation(
+        asset_server.load("vat/rat_med.vat"),
+        None,
+        &mut buffers,
+    ));
+    hybrid_config.vat_config.meshes = vec![
+        asset_server.load("blackrat_furless/rat_without_fur.glb#Mesh0/Primitive0"),
     ];
-    hybrid_config.vat_config.frame_counts = vec![30, 15];
-    hybrid_config.vat_config.playback_speeds = vec![0.5, 0.25];
+    hybrid_config.vat_config.materials = vec![rat_vat; 3];
+    hybrid_config.vat_config.frame_counts = vec![30; 3];
+    hybrid_config.vat_config.playback_speeds = vec![0.5; 3];
     
     // Mesh swap config for far distances
     hybrid_config.mesh_swap_config.mesh_handles = vec![
@@ -212,18 +254,38 @@ pub fn setup_lod_example_app(app: &mut App) {
     // Option 1: Animation-based LOD (similar to current implementation)
     app.add_plugins(LODPlugin::<Rat, AnimationLODStrategy>::default());
     
-    // Option 2: VAT-based LOD
+    // Option 2: VAT-based LOD, drawn instanced
     // app.add_plugins(LODPlugin::<Rat, VATLODStrategy>::default())
-    //    .add_plugins(VATMaterialPlugin);
+    //    .add_plugins((VATMaterialPlugin, VATInstancingPlugin));
+    
+    // Instanced VAT crowds without LOD (see `setup_vat_crowd`) need the same plugins
+    // app.add_plugins((VATMaterialPlugin, VATInstancingPlugin));
     
     // Option 3: Hybrid LOD (most flexible)
     // app.add_plugins(LODPlugin::<Rat, HybridLODStrategy>::default())
-    //    .add_plugins(VATMaterialPlugin);
+    //    .add_plugins((VATMaterialPlugin, VATInstancingPlugin));
     
     // Add debug system
     app.add_systems(Update, debug_lod_system);
 }
 
+// Marker component for a second LOD entity type
+#[derive(Component)]
+pub struct Cheese;
+
+/// Example app running two entity types with different strategies side by side.
+/// Both plugins share the `LODProcessingBudget`, rats get three quarters of it.
+pub fn setup_multi_entity_lod_example_app(app: &mut App) {
+    // Insert a custom budget before the plugins so they register their shares
+    // in it. One inserted afterwards only gets them back in `Plugin::finish`.
+    app.insert_resource(LODProcessingBudget::new(100))
+        .add_plugins(LODPlugin::<Rat, AnimationLODStrategy>::default().with_budget_weight(3.0))
+        .add_plugins(LODPlugin::<Cheese, MeshSwapLODStrategy>::default().with_budget_weight(1.0))
+        .insert_resource(LODLevels::<Rat>::new(create_standard_lod_levels()))
+        .insert_resource(LODLevels::<Cheese>::new(create_aggressive_lod_levels()))
+        .add_systems(Update, debug_lod_system);
+}
+
 // Resource definitions (these would come from your asset loading)
 #[derive(Resource)]
 pub struct RatModels {
@@ -0,0 +1,472 @@
+use bevy::prelude::*;
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::platform::collections::HashMap;
+use bevy::render::mesh::MeshTag;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::scene::{SceneInstance, SceneSpawner};
+
+use crate::lod_system::core::LODSystemSet;
+
+// Dithered cross-fades between LOD representations
+
+const FADE_ACTIVE_BIT: u32 = 0x4000_0000;
+const FADE_OUT_BIT: u32 = 0x8000_0000;
+const FADE_PROGRESS_MAX: u32 = 0xFFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LODFadeDirection {
+    In,
+    Out,
+}
+
+/// Screen-door fades a representation in or out.
+///
+/// Put it on the root of a representation (e.g. a scene instance). Every mesh
+/// below it, down to any with a fade of their own, gets the fade in its
+/// `MeshTag`, and meshes using `StandardMaterial` are drawn with the
+/// `LODDitherMaterial` variant for the duration. When the fade ends the
+/// component is removed, a faded out representation is hidden, and
+/// `LODFadeFinished` is triggered on the entity.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LODFade {
+    pub direction: LODFadeDirection,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl LODFade {
+    pub fn fade_in(duration: f32) -> Self {
+        Self { direction: LODFadeDirection::In, elapsed: 0.0, duration }
+    }
+
+    pub fn fade_out(duration: f32) -> Self {
+        Self { direction: LODFadeDirection::Out, elapsed: 0.0, duration }
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Turns the fade around from its current coverage
+    pub fn reversed(&self) -> Self {
+        let direction = match self.direction {
+            LODFadeDirection::In => LODFadeDirection::Out,
+            LODFadeDirection::Out => LODFadeDirection::In,
+        };
+        Self {
+            direction,
+            elapsed: self.duration - self.elapsed.min(self.duration),
+            duration: self.duration,
+        }
+    }
+
+    /// The fade packed for `lod_dither.wgsl`
+    pub fn mesh_tag(&self) -> u32 {
+        let progress = (self.progress() * FADE_PROGRESS_MAX as f32).round() as u32;
+        let direction = match self.direction {
+            LODFadeDirection::In => 0,
+            LODFadeDirection::Out => FADE_OUT_BIT,
+        };
+        FADE_ACTIVE_BIT | direction | progress
+    }
+}
+
+/// Marks meshes using their `MeshTag` for something else, e.g. a slot in a
+/// per-instance buffer. `LODFade` leaves their tag alone, their material reads
+/// `LODFade::mesh_tag` from its own instance data.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct LODFadeOwnTag;
+
+/// Triggered on an entity when its `LODFade` ends
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LODFadeFinished {
+    pub entity: Entity,
+    pub direction: LODFadeDirection,
+}
+
+/// Marks a representation an entity stopped showing while it fades out. It is
+/// despawned once it has.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct LODFadeGhost;
+
+/// Discards fragments by the fade in the mesh's `MeshTag`, in the prepass and
+/// shadow pass too
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct LODDitherExtension {}
+
+impl MaterialExtension for LODDitherExtension {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/lod_dither_material.wgsl".into()
+    }
+
+    fn prepass_fragment_shader() -> ShaderRef {
+        "shaders/lod_dither_prepass.wgsl".into()
+    }
+}
+
+/// Opaque materials are masked while they fade: bevy only runs the prepass and
+/// shadow pass fragment shaders, which do the dithering there, for masked ones.
+/// A cutoff of 0 discards nothing on its own.
+pub(crate) fn dithered_alpha_mode(alpha_mode: AlphaMode) -> AlphaMode {
+    match alpha_mode {
+        AlphaMode::Opaque => AlphaMode::Mask(0.0),
+        other => other,
+    }
+}
+
+pub type LODDitherMaterial = ExtendedMaterial<StandardMaterial, LODDitherExtension>;
+
+/// The material a mesh is restored to when its fade ends
+#[derive(Component)]
+struct LODDitherBase(Handle<StandardMaterial>);
+
+/// Dithered variants of the `StandardMaterial`s currently fading
+#[derive(Resource, Default)]
+struct LODDitherMaterials {
+    variants: HashMap<AssetId<StandardMaterial>, Handle<LODDitherMaterial>>,
+}
+
+/// Keeps `lod_system::dither` loaded for the shaders importing it
+#[derive(Resource)]
+struct LODDitherShader {
+    _library: Handle<Shader>,
+}
+
+/// Loads the `lod_system::dither` shader library if no plugin has yet
+pub(crate) fn load_dither_shader(app: &mut App) {
+    if !app.world().contains_resource::<LODDitherShader>() {
+        let library = app.world().resource::<AssetServer>().load("shaders/lod_dither.wgsl");
+        app.insert_resource(LODDitherShader { _library: library });
+    }
+}
+
+pub struct LODFadePlugin;
+
+impl Plugin for LODFadePlugin {
+    fn build(&self, app: &mut App) {
+        load_dither_shader(app);
+
+        app.add_plugins(MaterialPlugin::<LODDitherMaterial>::default())
+            .init_resource::<LODDitherMaterials>()
+            .add_event::<LODFadeFinished>()
+            .add_observer(despawn_faded_out_ghosts)
+            .add_systems(
+                PostUpdate,
+                (tick_lod_fades, apply_lod_fades)
+                    .chain()
+                    .after(LODSystemSet::Transition),
+            );
+    }
+}
+
+/// Advances fades, ending the finished ones
+fn tick_lod_fades(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fades: Query<(Entity, &mut LODFade)>,
+    fades_below: Query<(), With<LODFade>>,
+    descendants: Query<&Children>,
+    dithered: Query<&LODDitherBase>,
+    own_tags: Query<(), With<LODFadeOwnTag>>,
+) {
+    for (entity, mut fade) in fades.iter_mut() {
+        fade.elapsed += time.delta_secs();
+        if !fade.is_finished() {
+            continue;
+        }
+
+        // Back to the original materials
+        for mesh in faded_meshes(entity, &descendants, |child| fades_below.contains(child)) {
+            if let Ok(base) = dithered.get(mesh) {
+                commands.entity(mesh)
+                    .insert(MeshMaterial3d(base.0.clone()))
+                    .remove::<(MeshMaterial3d<LODDitherMaterial>, LODDitherBase, MeshTag)>();
+            } else if !own_tags.contains(mesh) {
+                commands.entity(mesh).remove::<MeshTag>();
+            }
+        }
+
+        let mut faded = commands.entity(entity);
+        faded.remove::<LODFade>();
+        if fade.direction == LODFadeDirection::Out {
+            faded.insert(Visibility::Hidden);
+        }
+        commands.trigger_targets(
+            LODFadeFinished { entity, direction: fade.direction },
+            entity,
+        );
+    }
+}
+
+/// Meshes `LODFade` tags, leaving those with `LODFadeOwnTag` alone
+type TaggableMesh = (With<Mesh3d>, Without<LODFadeOwnTag>);
+
+/// Tags every mesh under a fading entity with its fade, switching
+/// `StandardMaterial`s to their dithered variant. Runs every frame so meshes of
+/// scenes still spawning are picked up.
+fn apply_lod_fades(
+    mut commands: Commands,
+    mut dither_materials: ResMut<LODDitherMaterials>,
+    mut materials: ResMut<Assets<LODDitherMaterial>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    fades: Query<(Entity, &LODFade)>,
+    descendants: Query<&Children>,
+    meshes: Query<(Option<&MeshMaterial3d<StandardMaterial>>, Option<&MeshTag>), TaggableMesh>,
+) {
+    for (entity, fade) in fades.iter() {
+        let tag = fade.mesh_tag();
+
+        for mesh in faded_meshes(entity, &descendants, |child| fades.contains(child)) {
+            let Ok((standard, current_tag)) = meshes.get(mesh) else {
+                continue;
+            };
+            if current_tag.is_none_or(|current| current.0 != tag) {
+                commands.entity(mesh).insert(MeshTag(tag));
+            }
+
+            let Some(standard) = standard else {
+                continue;
+            };
+            let Some(base) = standard_materials.get(&standard.0) else {
+                continue;
+            };
+            let variant = dither_materials.variants
+                .entry(standard.0.id())
+                .or_insert_with(|| materials.add(LODDitherMaterial {
+                    base: StandardMaterial {
+                        alpha_mode: dithered_alpha_mode(base.alpha_mode),
+                        ..base.clone()
+                    },
+                    extension: LODDitherExtension {},
+                }))
+                .clone();
+
+            commands.entity(mesh)
+                .insert((MeshMaterial3d(variant), LODDitherBase(standard.0.clone())))
+                .remove::<MeshMaterial3d<StandardMaterial>>();
+        }
+    }
+}
+
+/// `entity` and everything below it, leaving out the subtrees of descendants
+/// with a fade of their own
+fn faded_meshes(
+    entity: Entity,
+    descendants: &Query<&Children>,
+    has_fade: impl Fn(Entity) -> bool,
+) -> Vec<Entity> {
+    let mut meshes = Vec::new();
+    let mut stack = vec![entity];
+    while let Some(mesh) = stack.pop() {
+        meshes.push(mesh);
+        let children = descendants.get(mesh).into_iter().flatten();
+        stack.extend(children.copied().filter(|child| !has_fade(*child)));
+    }
+    meshes
+}
+
+/// Despawns ghosts once they have faded out
+fn despawn_faded_out_ghosts(
+    trigger: Trigger<LODFadeFinished>,
+    mut commands: Commands,
+    ghosts: Query<(), With<LODFadeGhost>>,
+) {
+    let event = trigger.event();
+    if event.direction == LODFadeDirection::Out && ghosts.contains(event.entity) {
+        commands.entity(event.entity).despawn();
+    }
+}
+
+/// Fades `representation` out as a ghost, picking up from its current coverage
+/// if it was still fading in
+pub(crate) fn fade_out_ghost(world: &mut World, representation: Entity, fade_duration: f32) {
+    let fade_out = world.get::<LODFade>(representation)
+        .map(LODFade::reversed)
+        .unwrap_or(LODFade::fade_out(fade_duration));
+    world.entity_mut(representation).insert((fade_out, LODFadeGhost));
+}
+
+/// Whether a representation under `entity` is fading out, which whatever
+/// `entity` shows next should fade in against
+pub(crate) fn is_fading_out_under(world: &World, entity: Entity) -> bool {
+    world.get::<Children>(entity).is_some_and(|children| {
+        children.iter().any(|child| {
+            world.get::<LODFade>(child).is_some_and(|fade| fade.direction == LODFadeDirection::Out)
+        })
+    })
+}
+
+/// Moves the mesh `entity` shows itself to a ghost fading out under it, with
+/// the material it was restored to or will be. Returns whether it had one.
+pub(crate) fn ghost_mesh(world: &mut World, entity: Entity, fade_duration: f32) -> bool {
+    let Some(mesh) = world.get::<Mesh3d>(entity).cloned() else {
+        return false;
+    };
+    let material = world.get::<LODDitherBase>(entity).map(|base| base.0.clone())
+        .or_else(|| world.get::<MeshMaterial3d<StandardMaterial>>(entity).map(|material| material.0.clone()));
+
+    let ghost = spawn_ghost(world, entity, fade_duration);
+    let mut ghost = world.entity_mut(ghost);
+    ghost.insert(mesh);
+    if let Some(material) = material {
+        ghost.insert(MeshMaterial3d(material));
+    }
+    true
+}
+
+/// Moves the scene `entity` spawned itself with `SceneRoot` to a ghost fading
+/// out under it. Returns whether it had one.
+pub(crate) fn ghost_scene(world: &mut World, entity: Entity, fade_duration: f32) -> bool {
+    let Some(instance) = world.get::<SceneInstance>(entity).map(|instance| **instance) else {
+        return false;
+    };
+    let roots: Vec<Entity> = world.resource::<SceneSpawner>()
+        .iter_instance_entities(instance)
+        .filter(|scene_entity| world.get::<ChildOf>(*scene_entity).is_some_and(|parent| parent.parent() == entity))
+        .collect();
+
+    if !roots.is_empty() {
+        let ghost = spawn_ghost(world, entity, fade_duration);
+        for root in &roots {
+            world.entity_mut(*root).insert(ChildOf(ghost));
+        }
+    }
+    // Removing the `SceneRoot` makes the spawner forget the instance without
+    // despawning it
+    world.entity_mut(entity).remove::<(SceneRoot, SceneInstance)>();
+    !roots.is_empty()
+}
+
+fn spawn_ghost(world: &mut World, entity: Entity, fade_duration: f32) -> Entity {
+    let ghost = world.spawn((ChildOf(entity), Transform::IDENTITY, Visibility::Inherited)).id();
+    fade_out_ghost(world, ghost, fade_duration);
+    ghost
+}
+
+/// Cross-fades what `entity` shows itself, its mesh or its own scene, with
+/// whatever replaces it: the mesh and scene are moved to ghosts fading out
+/// under `entity`, and `entity` fades in. Queue it just before the replacement.
+pub struct CrossFadeRepresentation {
+    pub entity: Entity,
+    pub fade_duration: f32,
+}
+
+impl Command for CrossFadeRepresentation {
+    fn apply(self, world: &mut World) {
+        if self.fade_duration <= 0.0 || world.get_entity(self.entity).is_err() {
+            return;
+        }
+        let had_mesh = ghost_mesh(world, self.entity, self.fade_duration);
+        let had_scene = ghost_scene(world, self.entity, self.fade_duration);
+
+        if had_mesh || had_scene || is_fading_out_under(world, self.entity) {
+            world.entity_mut(self.entity).insert(LODFade::fade_in(self.fade_duration));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    fn fade_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<Assets<LODDitherMaterial>>();
+        world.init_resource::<LODDitherMaterials>();
+        world.add_observer(despawn_faded_out_ghosts);
+        world
+    }
+
+    fn mesh(world: &mut World) -> (Mesh3d, MeshMaterial3d<StandardMaterial>) {
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(Cuboid::default());
+        let material = world.resource_mut::<Assets<StandardMaterial>>().add(StandardMaterial::default());
+        (Mesh3d(mesh), MeshMaterial3d(material))
+    }
+
+    fn run_fades(world: &mut World, seconds: f32) {
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(tick_lod_fades).unwrap();
+        world.run_system_once(apply_lod_fades).unwrap();
+    }
+
+    #[test]
+    fn meshes_under_a_nested_fade_take_the_nested_fade() {
+        let mut world = fade_world();
+        let fade_in = LODFade::fade_in(1.0);
+        let fade_out = LODFade::fade_out(1.0);
+        let entity = world.spawn(fade_in).id();
+        let shown = mesh(&mut world);
+        let shown = world.spawn((shown, ChildOf(entity))).id();
+        let ghost = world.spawn((fade_out, ChildOf(entity))).id();
+        let ghost_mesh = mesh(&mut world);
+        let ghost_mesh = world.spawn((ghost_mesh, ChildOf(ghost))).id();
+
+        world.run_system_once(apply_lod_fades).unwrap();
+
+        assert_eq!(world.get::<MeshTag>(shown).unwrap().0, fade_in.mesh_tag());
+        assert_eq!(world.get::<MeshTag>(ghost_mesh).unwrap().0, fade_out.mesh_tag());
+    }
+
+    #[test]
+    fn opaque_materials_are_masked_while_they_fade() {
+        let mut world = fade_world();
+        let shown = mesh(&mut world);
+        let entity = world.spawn((shown, LODFade::fade_in(1.0))).id();
+
+        world.run_system_once(apply_lod_fades).unwrap();
+
+        let variant = world.get::<MeshMaterial3d<LODDitherMaterial>>(entity).unwrap();
+        let variant = world.resource::<Assets<LODDitherMaterial>>().get(&variant.0).unwrap();
+        assert_eq!(variant.base.alpha_mode, AlphaMode::Mask(0.0));
+    }
+
+    #[test]
+    fn cross_fading_a_mesh_fades_a_ghost_of_it_out() {
+        let mut world = fade_world();
+        let (old_mesh, old_material) = mesh(&mut world);
+        let entity = world.spawn((old_mesh.clone(), old_material.clone())).id();
+        run_fades(&mut world, 0.0);
+
+        CrossFadeRepresentation { entity, fade_duration: 0.5 }.apply(&mut world);
+        let (new_mesh, new_material) = mesh(&mut world);
+        world.entity_mut(entity).insert((new_mesh, new_material.clone()));
+        run_fades(&mut world, 0.25);
+
+        let ghost = world.get::<Children>(entity).unwrap()[0];
+        assert!(world.get::<LODFadeGhost>(ghost).is_some());
+        assert_eq!(world.get::<LODFade>(ghost).unwrap().direction, LODFadeDirection::Out);
+        assert_eq!(world.get::<Mesh3d>(ghost), Some(&old_mesh));
+        assert_eq!(world.get::<LODDitherBase>(ghost).unwrap().0, old_material.0);
+        assert_eq!(world.get::<LODFade>(entity).unwrap().direction, LODFadeDirection::In);
+
+        // Both fades end, the ghost goes and the entity is back to its material
+        run_fades(&mut world, 0.5);
+        assert!(world.get_entity(ghost).is_err());
+        assert!(world.get::<LODFade>(entity).is_none());
+        assert!(world.get::<MeshTag>(entity).is_none());
+        assert_eq!(world.get::<MeshMaterial3d<StandardMaterial>>(entity), Some(&new_material));
+    }
+
+    #[test]
+    fn cross_fading_nothing_does_not_fade_in() {
+        let mut world = fade_world();
+        let entity = world.spawn(Transform::default()).id();
+
+        CrossFadeRepresentation { entity, fade_duration: 0.5 }.apply(&mut world);
+
+        assert!(world.get::<LODFade>(entity).is_none());
+        assert!(world.get::<Children>(entity).is_none());
+    }
+}
@@ -7,10 +7,13 @@
 //! - Mesh swapping LOD
 //! - Hybrid approaches
 //! 
+//! Entities are bucketed in a `SpatialGrid` so the per-frame distance pass only
+//! touches entities that moved, plus whole cells when the camera moves.
+//! 
 //! # Example
-//! ```rust
+//! ```rust,no_run
 //! use bevy::prelude::*;
-//! use lod_system::*;
+//! use hairyrat::lod_system::prelude::*;
 //! 
 //! #[derive(Component)]
 //! struct MyEntity;
@@ -23,45 +26,101 @@
 //! }
 //! ```
 
+pub mod animation;
+pub mod bake;
 pub mod core;
+pub mod fade;
+pub mod pool;
+pub mod spatial;
 pub mod strategies;
+pub mod validation;
 pub mod vat;
+pub mod vat_instancing;
 pub mod examples;
 
 // Re-export commonly used types
 pub use core::{
     LODStrategy, LODLevel, LODState, LODDistance, LODLevels,
     LODProcessingBudget, LODPlugin,
+    LODDistanceMetric, LODDistanceSettings, LODBoundingRadius, LODView,
+    LODCamera, LODCameraFilter, LODSystemSet, LODBudgetStats, LODTransitioned,
+};
+
+pub use spatial::{SpatialGrid, SpatialCell};
+
+pub use animation::{
+    AnimationScheduler, AnimationSchedulerPlugin, AnimationTimeSlice,
+    AnimationLODSource, TimeSlicedAnimationPlayer,
+    AnimationHandoff, AnimationHandoffPlugin,
+};
+
+pub use fade::{
+    LODFade, LODFadeDirection, LODFadeFinished, LODFadePlugin, LODFadeOwnTag,
+    LODFadeGhost, CrossFadeRepresentation, LODDitherExtension, LODDitherMaterial,
+};
+
+pub use pool::{
+    ScenePool, ScenePoolPlugin, ScenePoolStats, PooledScene, PooledSceneInstance,
+    SwapPooledScene,
 };
 
 pub use strategies::{
-    AnimationLODStrategy, AnimationLODConfig, AnimationLODData,
+    AnimationLODStrategy, AnimationLODConfig, AnimationLODData, DisabledAnimationMode,
+    LODTransitionCosts,
     VATLODStrategy, VATLODConfig, VATLODData,
+    VATRepresentation, SwapVATRepresentation, RemoveVATRepresentation,
     MeshSwapLODStrategy, MeshSwapLODConfig, MeshSwapLODData,
     HybridLODStrategy, HybridLODConfig, HybridLODData,
     create_standard_lod_levels, create_aggressive_lod_levels,
+    create_screen_size_lod_levels,
 };
 
 pub use vat::{
-    VATMaterial, VATAnimationState, VATBundle, VATMaterialPlugin,
+    VATMaterial, VATMaterialUniform, VATAnimationState, VATBundle, VATMaterialPlugin,
     create_vat_material, calculate_vat_texture_dimensions,
-    VATConfig, SimplifiedVAT,
+    VATConfig, VATEncoding, VATPositionEncoding, VATNormalEncoding, SimplifiedVAT,
+    VATAnimation, VATAnimationLoader, VATAnimationLoaderError, VATClip, VATFile,
+    VATVertexSample, sample_vat_vertex, decode_vat_texel, vat_frame_blend,
+    octahedral_encode, octahedral_decode,
+    VATAnimatedMaterial, VATPlayback, VATLODSource,
 };
 
+pub use vat_instancing::{
+    VATInstancedMaterial, VATInstancedMaterialUniform, VATInstanceData, VATInstancingPlugin,
+};
+
+pub use bake::{bake_vat, BakedVAT, VATBakeSettings, VATBakeError, VATEncodingReport, VATError};
+
+pub use validation::{validate_lod_dither_shaders, validate_vat_shader, ShaderValidationError};
+
 /// Prelude for convenient imports
 pub mod prelude {
 pub use crate::lod_system::{
     // Core types
     core::{LODStrategy, LODLevel, LODState, LODDistance, LODLevels,
-           LODProcessingBudget, LODPlugin},
+           LODProcessingBudget, LODPlugin, LODDistanceMetric, LODBoundingRadius,
+           LODCamera, LODCameraFilter, LODSystemSet, LODTransitioned},
+    
+    // Spatial partitioning
+    spatial::SpatialGrid,
+    
+    // Animation time slicing
+    animation::{AnimationScheduler, AnimationSchedulerPlugin, AnimationTimeSlice,
+                AnimationHandoffPlugin},
+    
+    // Scene pooling and cross-fades
+    pool::{ScenePool, ScenePoolPlugin},
+    fade::{LODFade, LODFadePlugin},
     
     // Strategies
     strategies::{AnimationLODStrategy, AnimationLODData, VATLODStrategy, 
                  MeshSwapLODStrategy, HybridLODStrategy,
-                 create_standard_lod_levels, create_aggressive_lod_levels},
+                 create_standard_lod_levels, create_aggressive_lod_levels,
+                 create_screen_size_lod_levels},
     
     // VAT support
     vat::{VATMaterial, VATAnimationState, VATBundle, VATMaterialPlugin,
-          create_vat_material, calculate_vat_texture_dimensions},
+          VATAnimation, create_vat_material, calculate_vat_texture_dimensions},
+    vat_instancing::{VATInstancedMaterial, VATInstancingPlugin},
 };
 }
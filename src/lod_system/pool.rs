@@ -0,0 +1,462 @@
+use bevy::prelude::*;
+use bevy::app::Animation;
+use bevy::platform::collections::HashMap;
+use bevy::scene::{SceneInstance, SceneSpawner};
+
+use crate::lod_system::animation::{AnimationHandoff, AnimationTimeSlice, TimeSlicedAnimationPlayer};
+use crate::lod_system::fade::{
+    LODFade, LODFadeDirection, LODFadeFinished, ghost_scene, is_fading_out_under,
+};
+
+// Scene instance pooling for scene-swapping LOD strategies
+
+/// Root of a pooled scene instance
+#[derive(Component, Debug)]
+pub struct PooledScene {
+    pub scene: Handle<Scene>,
+    pub in_use: bool,
+    player: Option<Entity>,
+}
+
+/// The pooled scene instance currently shown under a LOD entity
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PooledSceneInstance(pub Entity);
+
+/// Running totals for the `ScenePool`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScenePoolStats {
+    pub hits: u32,        // Swaps served by an idle instance
+    pub misses: u32,      // Swaps that had to spawn a new instance
+    pub evictions: u32,   // Released instances despawned because the pool was full
+    pub prewarmed: u32,
+}
+
+/// Idle, pre-spawned scene hierarchies keyed by scene.
+///
+/// Swapping a LOD entity's scene reparents an idle instance under it and parks
+/// the old one (hidden, without its `AnimationPlayer`) instead of spawning and
+/// despawning a whole skinned hierarchy. At most `max_idle_per_scene`
+/// instances are kept per scene, extra ones are despawned on release.
+#[derive(Resource)]
+pub struct ScenePool {
+    pub max_idle_per_scene: usize,
+    pub stats: ScenePoolStats,
+    idle: HashMap<Handle<Scene>, Vec<Entity>>,
+    pending_prewarm: Vec<(Handle<Scene>, usize)>,
+}
+
+impl Default for ScenePool {
+    fn default() -> Self {
+        Self::new(128)
+    }
+}
+
+impl ScenePool {
+    pub fn new(max_idle_per_scene: usize) -> Self {
+        Self {
+            max_idle_per_scene,
+            stats: ScenePoolStats::default(),
+            idle: HashMap::default(),
+            pending_prewarm: Vec::new(),
+        }
+    }
+
+    /// Spawns up to `count` idle instances of `scene` on the next update
+    pub fn prewarm(&mut self, scene: Handle<Scene>, count: usize) {
+        self.pending_prewarm.push((scene, count));
+    }
+
+    pub fn idle_count(&self, scene: &Handle<Scene>) -> usize {
+        self.idle.get(scene).map_or(0, Vec::len)
+    }
+
+    pub fn total_idle(&self) -> usize {
+        self.idle.values().map(Vec::len).sum()
+    }
+}
+
+/// Pools scene instances for `MeshSwapLODConfig::pool_scenes`
+pub struct ScenePoolPlugin {
+    pub max_idle_per_scene: usize,
+}
+
+impl Default for ScenePoolPlugin {
+    fn default() -> Self {
+        Self { max_idle_per_scene: 128 }
+    }
+}
+
+impl Plugin for ScenePoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScenePool::new(self.max_idle_per_scene))
+            .add_systems(PreUpdate, prewarm_scene_pool)
+            .add_systems(PostUpdate, park_idle_scene_players.before(Animation))
+            .add_observer(release_faded_out_instance);
+    }
+}
+
+/// Shows a pooled instance of `scene` under `entity`, releasing the scene it
+/// showed before. Falls back to inserting a `SceneRoot` without a `ScenePool`.
+///
+/// With a `fade_duration` the new instance cross-fades with `LODFade` against
+/// whatever the entity showed, which is released once it has faded out.
+pub struct SwapPooledScene {
+    pub entity: Entity,
+    pub scene: Handle<Scene>,
+    pub fade_duration: f32,
+}
+
+impl Command for SwapPooledScene {
+    fn apply(self, world: &mut World) {
+        if !world.contains_resource::<ScenePool>() {
+            if let Ok(mut entity) = world.get_entity_mut(self.entity) {
+                entity.insert(SceneRoot(self.scene));
+            }
+            return;
+        }
+        let Ok(entity) = world.get_entity(self.entity) else {
+            return;
+        };
+
+        let current = entity.get::<PooledSceneInstance>().map(|instance| instance.0);
+        let showing = current
+            .and_then(|instance| world.get::<PooledScene>(instance))
+            .is_some_and(|pooled| pooled.scene == self.scene);
+        if showing {
+            return;
+        }
+
+        let handoff = capture_handoff(world, current.unwrap_or(self.entity), self.entity);
+        let fade = self.fade_duration > 0.0;
+
+        // Release whatever the entity shows now, pooled or its own scene
+        if let Some(instance) = current {
+            release_or_fade_out(world, instance, self.fade_duration);
+        } else if fade {
+            ghost_scene(world, self.entity, self.fade_duration);
+        } else if let Some(instance) = world.get::<SceneInstance>(self.entity).map(|instance| **instance) {
+            world.resource_mut::<SceneSpawner>().despawn_instance(instance);
+            world.entity_mut(self.entity).remove::<(SceneRoot, SceneInstance)>();
+        }
+
+        let instance = acquire_instance(world, self.scene);
+        world.entity_mut(instance).insert((
+            ChildOf(self.entity),
+            Transform::IDENTITY,
+            Visibility::Inherited,
+        ));
+        // E.g. the previous instance, or a VAT representation taken down
+        if fade && is_fading_out_under(world, self.entity) {
+            world.entity_mut(instance).insert(LODFade::fade_in(self.fade_duration));
+        }
+
+        let mut entity = world.entity_mut(self.entity);
+        entity.insert(PooledSceneInstance(instance));
+        if let Some(handoff) = handoff {
+            entity.insert(handoff);
+        }
+    }
+}
+
+/// Stops showing any scene under `entity`, pooled or its own, keeping its
+/// animation state as an `AnimationHandoff` for the next scene it shows. With a
+/// `fade_duration` the scene fades out before it is released.
+pub(crate) fn release_scene(world: &mut World, entity: Entity, fade_duration: f32) {
+    let current = world.get::<PooledSceneInstance>(entity).map(|instance| instance.0);
+    if let Some(handoff) = capture_handoff(world, current.unwrap_or(entity), entity) {
+        world.entity_mut(entity).insert(handoff);
+    }
+
+    if let Some(instance) = current {
+        release_or_fade_out(world, instance, fade_duration);
+        world.entity_mut(entity).remove::<PooledSceneInstance>();
+    } else if fade_duration > 0.0 {
+        ghost_scene(world, entity, fade_duration);
+    } else {
+        if let Some(instance) = world.get::<SceneInstance>(entity).map(|instance| **instance) {
+            world.resource_mut::<SceneSpawner>().despawn_instance(instance);
+        }
+        world.entity_mut(entity).remove::<(SceneRoot, SceneInstance)>();
+    }
+}
+
+/// Releases a pooled instance, or with a `fade_duration` fades it out for
+/// `release_faded_out_instance` to release
+fn release_or_fade_out(world: &mut World, instance: Entity, fade_duration: f32) {
+    if fade_duration <= 0.0 {
+        release_instance(world, instance);
+        return;
+    }
+    // Picks up from the current coverage if it was still fading in
+    let fade_out = world.get::<LODFade>(instance)
+        .map(LODFade::reversed)
+        .unwrap_or(LODFade::fade_out(fade_duration));
+    world.entity_mut(instance).insert(fade_out);
+}
+
+/// Captures the animation state of the first player under `scene`, shown by
+/// `entity`
+fn capture_handoff(world: &mut World, scene: Entity, entity: Entity) -> Option<AnimationHandoff> {
+    let player = find_animation_player(world, scene)?;
+
+    let has_paused = world.get::<TimeSlicedAnimationPlayer>(player)
+        .is_some_and(TimeSlicedAnimationPlayer::has_paused);
+    let skipped = if has_paused {
+        world.get_mut::<AnimationTimeSlice>(entity)
+            .map(|mut slice| std::mem::take(&mut slice.accumulated_time))
+            .unwrap_or(0.0)
+    } else {
+        0.0
+    };
+
+    // Animations have already been advanced this frame
+    let captured_at = world.resource::<Time>().elapsed_secs();
+    let player = world.entity(player);
+    Some(AnimationHandoff::capture(
+        player.get::<AnimationPlayer>()?,
+        player.get::<AnimationTransitions>(),
+        player.get::<AnimationGraphHandle>(),
+        player.get::<TimeSlicedAnimationPlayer>(),
+        skipped,
+        captured_at,
+    ))
+}
+
+pub(crate) fn find_animation_player(world: &World, root: Entity) -> Option<Entity> {
+    let mut stack = vec![root];
+    while let Some(entity) = stack.pop() {
+        let entity = world.get_entity(entity).ok()?;
+        if entity.contains::<AnimationPlayer>() {
+            return Some(entity.id());
+        }
+        if let Some(children) = entity.get::<Children>() {
+            stack.extend(children.iter());
+        }
+    }
+    None
+}
+
+/// Takes an idle instance of `scene` from the pool, or spawns one
+fn acquire_instance(world: &mut World, scene: Handle<Scene>) -> Entity {
+    loop {
+        let idle = world.resource_mut::<ScenePool>()
+            .idle
+            .get_mut(&scene)
+            .and_then(Vec::pop);
+        let Some(instance) = idle else {
+            break;
+        };
+        let Some(mut pooled) = world.get_mut::<PooledScene>(instance) else {
+            // Despawned while idle
+            continue;
+        };
+        pooled.in_use = true;
+        let player = pooled.player;
+
+        // A re-added player is picked up like a newly spawned scene's
+        if let Some(player) = player {
+            world.entity_mut(player).insert(AnimationPlayer::default());
+        }
+        world.resource_mut::<ScenePool>().stats.hits += 1;
+        return instance;
+    }
+
+    world.resource_mut::<ScenePool>().stats.misses += 1;
+    world.spawn((
+        SceneRoot(scene.clone()),
+        PooledScene { scene, in_use: true, player: None },
+        Transform::IDENTITY,
+        Visibility::Inherited,
+    )).id()
+}
+
+/// Parks `instance` in the pool, or despawns it if the pool is full
+fn release_instance(world: &mut World, instance: Entity) {
+    let Some(pooled) = world.get::<PooledScene>(instance) else {
+        return;
+    };
+    let scene = pooled.scene.clone();
+    let player = pooled.player;
+
+    let mut pool = world.resource_mut::<ScenePool>();
+    if pool.idle_count(&scene) >= pool.max_idle_per_scene {
+        pool.stats.evictions += 1;
+        if let Some(scene_instance) = world.get::<SceneInstance>(instance).map(|instance| **instance) {
+            world.resource_mut::<SceneSpawner>().despawn_instance(scene_instance);
+        }
+        world.entity_mut(instance).despawn();
+        return;
+    }
+    pool.idle.entry(scene).or_default().push(instance);
+
+    // Idle instances don't animate
+    if let Some(player) = player {
+        world.entity_mut(player)
+            .remove::<(AnimationPlayer, AnimationTransitions, TimeSlicedAnimationPlayer)>();
+    }
+    let mut instance = world.entity_mut(instance);
+    instance.remove::<ChildOf>().insert(Visibility::Hidden);
+    if let Some(mut pooled) = instance.get_mut::<PooledScene>() {
+        pooled.in_use = false;
+    }
+}
+
+/// Releases pooled instances once they have faded out
+fn release_faded_out_instance(
+    trigger: Trigger<LODFadeFinished>,
+    mut commands: Commands,
+    instances: Query<(), With<PooledScene>>,
+) {
+    let event = trigger.event();
+    if event.direction == LODFadeDirection::Out && instances.contains(event.entity) {
+        let instance = event.entity;
+        commands.queue(move |world: &mut World| release_instance(world, instance));
+    }
+}
+
+/// Spawns the instances requested with `ScenePool::prewarm`
+fn prewarm_scene_pool(mut commands: Commands, mut pool: ResMut<ScenePool>) {
+    if pool.pending_prewarm.is_empty() {
+        return;
+    }
+
+    for (scene, count) in std::mem::take(&mut pool.pending_prewarm) {
+        let count = count.min(pool.max_idle_per_scene.saturating_sub(pool.idle_count(&scene)));
+        for _ in 0..count {
+            let instance = commands.spawn((
+                SceneRoot(scene.clone()),
+                PooledScene { scene: scene.clone(), in_use: false, player: None },
+                Transform::IDENTITY,
+                Visibility::Hidden,
+            )).id();
+            pool.idle.entry(scene.clone()).or_default().push(instance);
+        }
+        pool.stats.prewarmed += count as u32;
+    }
+}
+
+/// Records the player of each pooled instance, removing it while idle so
+/// parked hierarchies aren't animated
+fn park_idle_scene_players(
+    mut commands: Commands,
+    players: Query<Entity, Added<AnimationPlayer>>,
+    parents: Query<&ChildOf>,
+    mut instances: Query<&mut PooledScene>,
+) {
+    for player in players.iter() {
+        let Some(root) = parents.iter_ancestors(player).find(|ancestor| instances.contains(*ancestor)) else {
+            continue;
+        };
+        let Ok(mut pooled) = instances.get_mut(root) else {
+            continue;
+        };
+
+        pooled.player = Some(player);
+        if !pooled.in_use {
+            commands.entity(player).remove::<(AnimationPlayer, AnimationTransitions)>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn pool_world(max_idle_per_scene: usize) -> World {
+        let mut world = World::new();
+        world.insert_resource(ScenePool::new(max_idle_per_scene));
+        world.insert_resource(Time::<()>::default());
+        world
+    }
+
+    /// Two scenes, never spawned since the pool only needs their handles
+    fn scenes() -> (Handle<Scene>, Handle<Scene>) {
+        let mut scenes = Assets::<Scene>::default();
+        (scenes.add(Scene::new(World::new())), scenes.add(Scene::new(World::new())))
+    }
+
+    fn swap(world: &mut World, entity: Entity, scene: &Handle<Scene>) -> Entity {
+        SwapPooledScene { entity, scene: scene.clone(), fade_duration: 0.0 }.apply(world);
+        world.get::<PooledSceneInstance>(entity).unwrap().0
+    }
+
+    fn pool(world: &World) -> &ScenePool {
+        world.resource::<ScenePool>()
+    }
+
+    fn is_idle(world: &World, instance: Entity) -> bool {
+        let pooled = world.get::<PooledScene>(instance).unwrap();
+        !pooled.in_use
+            && world.get::<ChildOf>(instance).is_none()
+            && world.get::<Visibility>(instance) == Some(&Visibility::Hidden)
+    }
+
+    #[test]
+    fn released_instances_are_reused_by_the_next_swap_to_their_scene() {
+        let mut world = pool_world(4);
+        let (near, far) = scenes();
+        let rat = world.spawn_empty().id();
+
+        let first = swap(&mut world, rat, &near);
+        assert_eq!(world.get::<ChildOf>(first).unwrap().parent(), rat);
+        assert!(world.get::<PooledScene>(first).unwrap().in_use);
+
+        let second = swap(&mut world, rat, &far);
+        assert!(is_idle(&world, first));
+        assert_eq!(pool(&world).idle_count(&near), 1);
+
+        // Back to the first scene takes its idle instance
+        assert_eq!(swap(&mut world, rat, &near), first);
+        assert!(world.get::<PooledScene>(first).unwrap().in_use);
+        assert!(is_idle(&world, second));
+
+        // Swapping to the scene already shown does nothing
+        assert_eq!(swap(&mut world, rat, &near), first);
+
+        let stats = pool(&world).stats;
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 2, 0));
+        assert_eq!(pool(&world).total_idle(), 1);
+    }
+
+    #[test]
+    fn releases_past_capacity_are_despawned() {
+        let mut world = pool_world(1);
+        let (near, far) = scenes();
+        let rats = [world.spawn_empty().id(), world.spawn_empty().id()];
+        let shown = rats.map(|rat| swap(&mut world, rat, &near));
+
+        for rat in rats {
+            swap(&mut world, rat, &far);
+        }
+        assert!(is_idle(&world, shown[0]));
+        assert!(world.get_entity(shown[1]).is_err());
+        assert_eq!(pool(&world).idle_count(&near), 1);
+        assert_eq!(pool(&world).stats.evictions, 1);
+    }
+
+    #[test]
+    fn prewarming_fills_the_pool_up_to_capacity() {
+        let mut world = pool_world(2);
+        let (near, _) = scenes();
+        world.resource_mut::<ScenePool>().prewarm(near.clone(), 3);
+        world.run_system_once(prewarm_scene_pool).unwrap();
+
+        assert_eq!(pool(&world).idle_count(&near), 2);
+        assert_eq!(pool(&world).stats.prewarmed, 2);
+        let mut idle = world.query_filtered::<Entity, With<PooledScene>>();
+        let idle: Vec<_> = idle.iter(&world).collect();
+        assert!(idle.iter().all(|instance| is_idle(&world, *instance)));
+
+        // Swaps are served from the prewarmed instances, further requests
+        // only top the pool back up
+        let rat = world.spawn_empty().id();
+        assert!(idle.contains(&swap(&mut world, rat, &near)));
+        world.resource_mut::<ScenePool>().prewarm(near.clone(), 3);
+        world.run_system_once(prewarm_scene_pool).unwrap();
+        assert_eq!(pool(&world).idle_count(&near), 2);
+
+        let stats = pool(&world).stats;
+        assert_eq!((stats.hits, stats.misses, stats.prewarmed), (1, 0, 3));
+    }
+}
@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use bevy::platform::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+// Spatial partitioning for the LOD distance pass
+
+/// Where an entity was last bucketed in a `SpatialGrid`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpatialCell {
+    pub grid_position: IVec3,
+    pub last_position: Vec3,
+}
+
+/// Uniform grid bucketing every `T` entity by its world-space position.
+///
+/// Entities are only re-bucketed when their `GlobalTransform` changes, and cells
+/// further than `far_cell_distance` from the camera are assigned a single LOD
+/// distance for the whole cell, so the LOD pass scales with moved entities and
+/// cells rather than with the total entity count. While the camera is still,
+/// far cells are only revisited once entities enter or leave them, as recorded
+/// in `dirty_cells`.
+#[derive(Resource)]
+pub struct SpatialGrid<T> {
+    pub cell_size: f32,
+    pub far_cell_distance: f32,
+    pub cells: HashMap<IVec3, Vec<Entity>>,
+    /// Cells entities entered or left since `update_spatial_grid` last ran
+    pub dirty_cells: HashSet<IVec3>,
+    entities: HashMap<Entity, SpatialCell>,
+    cell_levels: HashMap<IVec3, u8>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Default for SpatialGrid<T> {
+    fn default() -> Self {
+        Self::new(4.0, 40.0)
+    }
+}
+
+impl<T> SpatialGrid<T> {
+    pub fn new(cell_size: f32, far_cell_distance: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            far_cell_distance,
+            cells: HashMap::default(),
+            dirty_cells: HashSet::default(),
+            entities: HashMap::default(),
+            cell_levels: HashMap::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn cell_of(&self, position: Vec3) -> IVec3 {
+        (position / self.cell_size).floor().as_ivec3()
+    }
+
+    pub fn cell_center(&self, cell: IVec3) -> Vec3 {
+        (cell.as_vec3() + Vec3::splat(0.5)) * self.cell_size
+    }
+
+    /// Distance from a cell's center to its corners
+    pub fn cell_radius(&self) -> f32 {
+        Vec3::splat(self.cell_size * 0.5).length()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<SpatialCell> {
+        self.entities.get(&entity).copied()
+    }
+
+    /// Inserts or moves an entity, returning its cell
+    pub fn insert(&mut self, entity: Entity, position: Vec3) -> IVec3 {
+        let cell = self.cell_of(position);
+
+        if let Some(previous) = self.entities.get_mut(&entity) {
+            previous.last_position = position;
+            if previous.grid_position == cell {
+                return cell;
+            }
+            let old_cell = previous.grid_position;
+            previous.grid_position = cell;
+            self.remove_from_cell(entity, old_cell);
+        } else {
+            self.entities.insert(entity, SpatialCell {
+                grid_position: cell,
+                last_position: position,
+            });
+        }
+
+        self.cells.entry(cell).or_default().push(entity);
+        self.dirty_cells.insert(cell);
+        cell
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(previous) = self.entities.remove(&entity) {
+            self.remove_from_cell(entity, previous.grid_position);
+        }
+    }
+
+    fn remove_from_cell(&mut self, entity: Entity, cell: IVec3) {
+        if let Some(bucket) = self.cells.get_mut(&cell) {
+            if let Some(index) = bucket.iter().position(|e| *e == entity) {
+                bucket.swap_remove(index);
+            }
+            if bucket.is_empty() {
+                self.cells.remove(&cell);
+                self.cell_levels.remove(&cell);
+            }
+        }
+        self.dirty_cells.insert(cell);
+    }
+
+    pub fn entities_in_cell(&self, cell: IVec3) -> &[Entity] {
+        self.cells.get(&cell).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Entities in `cell` and the 26 cells around it
+    pub fn neighbours(&self, cell: IVec3) -> impl Iterator<Item = Entity> + '_ {
+        (-1..=1).flat_map(move |x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| {
+            cell + IVec3::new(x, y, z)
+        })))
+        .flat_map(|c| self.entities_in_cell(c).iter().copied())
+    }
+
+    /// Entities whose last known position is within `radius` of `center`
+    pub fn query_radius(&self, center: Vec3, radius: f32) -> Vec<Entity> {
+        let min = self.cell_of(center - Vec3::splat(radius));
+        let max = self.cell_of(center + Vec3::splat(radius));
+        let radius_squared = radius * radius;
+        let mut result = Vec::new();
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    for entity in self.entities_in_cell(IVec3::new(x, y, z)) {
+                        let Some(cell) = self.entities.get(entity) else {
+                            continue;
+                        };
+                        if cell.last_position.distance_squared(center) <= radius_squared {
+                            result.push(*entity);
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Returns true if the whole cell is far enough from `camera_position` to
+    /// share a single LOD distance
+    pub fn is_far_cell(&self, cell: IVec3, camera_position: Vec3) -> bool {
+        self.cell_center(cell).distance(camera_position) - self.cell_radius() > self.far_cell_distance
+    }
+
+    /// Records the level assigned to a far cell, returning true if it changed
+    pub(crate) fn set_cell_level(&mut self, cell: IVec3, level: u8) -> bool {
+        self.cell_levels.insert(cell, level) != Some(level)
+    }
+
+    /// Forgets the level of a cell that is handled per entity again
+    pub(crate) fn clear_cell_level(&mut self, cell: IVec3) {
+        self.cell_levels.remove(&cell);
+    }
+}
+
+/// `T` entities whose `GlobalTransform` changed since the system last ran
+pub(crate) type MovedFilter<T> = (With<T>, Changed<GlobalTransform>);
+
+/// Re-buckets moved `T` entities and drops despawned ones
+pub fn update_spatial_grid<T: Component>(
+    mut grid: ResMut<SpatialGrid<T>>,
+    moved_query: Query<(Entity, &GlobalTransform), MovedFilter<T>>,
+    mut removed: RemovedComponents<T>,
+) {
+    grid.dirty_cells.clear();
+
+    for entity in removed.read() {
+        grid.remove(entity);
+    }
+
+    for (entity, transform) in moved_query.iter() {
+        grid.insert(entity, transform.translation());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestRat;
+
+    /// A grid of 4 unit cells with entities `0..positions.len()` inserted at
+    /// `positions`
+    fn grid_with(positions: &[Vec3]) -> (SpatialGrid<TestRat>, Vec<Entity>) {
+        let mut grid = SpatialGrid::new(4.0, 40.0);
+        let entities = (0..positions.len() as u32).map(Entity::from_raw).collect::<Vec<_>>();
+        for (entity, position) in entities.iter().zip(positions) {
+            grid.insert(*entity, *position);
+        }
+        (grid, entities)
+    }
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort();
+        entities
+    }
+
+    #[test]
+    fn cell_boundaries_belong_to_the_cell_above() {
+        let grid = SpatialGrid::<TestRat>::new(4.0, 40.0);
+        assert_eq!(grid.cell_of(Vec3::ZERO), IVec3::ZERO);
+        assert_eq!(grid.cell_of(Vec3::splat(3.999)), IVec3::ZERO);
+        assert_eq!(grid.cell_of(Vec3::new(4.0, 0.0, 0.0)), IVec3::X);
+        assert_eq!(grid.cell_of(Vec3::new(-0.001, 0.0, -4.0)), IVec3::new(-1, 0, -1));
+        assert_eq!(grid.cell_of(Vec3::new(0.0, -4.001, 0.0)), IVec3::new(0, -2, 0));
+    }
+
+    #[test]
+    fn neighbours_are_the_surrounding_block_of_cells() {
+        let (grid, entities) = grid_with(&[
+            Vec3::new(1.0, 1.0, 1.0),    // Cell (0, 0, 0)
+            Vec3::new(-0.5, 5.0, 7.9),   // Cell (-1, 1, 1), a corner neighbour
+            Vec3::new(8.0, 0.0, 0.0),    // Cell (2, 0, 0), two cells over
+            Vec3::new(-4.5, 1.0, 1.0),   // Cell (-2, 0, 0), two cells the other way
+        ]);
+
+        let neighbours = sorted(grid.neighbours(IVec3::ZERO).collect());
+        assert_eq!(neighbours, [entities[0], entities[1]]);
+
+        // From the far cell only its own entity and nothing across the gap
+        let neighbours = sorted(grid.neighbours(IVec3::new(2, 0, 0)).collect());
+        assert_eq!(neighbours, [entities[2]]);
+    }
+
+    #[test]
+    fn neighbours_follow_entities_that_moved() {
+        let (mut grid, entities) = grid_with(&[Vec3::new(1.0, 1.0, 1.0)]);
+        grid.insert(entities[0], Vec3::new(12.0, 1.0, 1.0));
+
+        assert_eq!(grid.neighbours(IVec3::ZERO).count(), 0);
+        assert_eq!(sorted(grid.neighbours(IVec3::new(2, 0, 0)).collect()), [entities[0]]);
+    }
+
+    #[test]
+    fn query_radius_includes_the_radius_and_crosses_cell_boundaries() {
+        let center = Vec3::new(3.5, 1.0, 1.0);
+        let (grid, entities) = grid_with(&[
+            center + Vec3::new(2.0, 0.0, 0.0),    // Exactly on the radius, in the next cell
+            center + Vec3::new(-1.0, -1.0, 0.0),  // Inside, same cell
+            center + Vec3::new(0.0, 0.0, -2.01),  // Just outside, in the cell behind
+            center + Vec3::new(1.5, 1.5, 0.0),    // Inside the bounding cells, outside the sphere
+        ]);
+
+        assert_eq!(sorted(grid.query_radius(center, 2.0)), [entities[0], entities[1]]);
+        assert_eq!(grid.query_radius(center, 0.5), []);
+    }
+
+    #[test]
+    fn query_radius_skips_removed_entities_and_uses_the_last_position() {
+        let (mut grid, entities) = grid_with(&[Vec3::new(1.0, 1.0, 1.0), Vec3::new(2.0, 1.0, 1.0)]);
+        grid.remove(entities[0]);
+        // Moving inside the same cell still updates the position queried
+        grid.insert(entities[1], Vec3::new(3.9, 3.9, 3.9));
+
+        assert_eq!(grid.query_radius(Vec3::new(1.0, 1.0, 1.0), 1.5), []);
+        assert_eq!(grid.query_radius(Vec3::new(3.0, 3.9, 3.9), 1.0), [entities[1]]);
+    }
+}
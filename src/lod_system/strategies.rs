@@ -1,5 +1,44 @@
 use bevy::prelude::*;
+use std::time::Duration;
 use crate::lod_system::core::*;
+use crate::lod_system::animation::{AnimationHandoff, AnimationTimeSlice, TimeSlicedAnimationPlayer, clip_duration};
+use crate::lod_system::fade::{CrossFadeRepresentation, LODFade, fade_out_ghost, is_fading_out_under};
+use crate::lod_system::pool::{PooledSceneInstance, SwapPooledScene, find_animation_player, release_scene};
+use crate::lod_system::vat::VATAnimationState;
+use crate::lod_system::vat_instancing::VATInstancedMaterial;
+
+/// Estimated per-transition costs each strategy charges against the time cap
+/// of `LODProcessingBudget`. The real work (e.g. spawning a scene) happens when
+/// the commands are applied, after the budget has been spent, so it can't be
+/// timed per transition. The defaults only keep the kinds of transition in
+/// proportion, a scene spawn being two orders of magnitude above a component
+/// toggle; profile the frame's command application for your own scenes and
+/// set them from that.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LODTransitionCosts {
+    /// Toggling components and visibility
+    pub component_toggle: Duration,
+    /// Swapping the VAT material and mesh
+    pub vat_swap: Duration,
+    /// Swapping the mesh and material
+    pub mesh_swap: Duration,
+    /// Despawning a scene and spawning another
+    pub scene_swap: Duration,
+    /// Swapping scenes through the `ScenePool`
+    pub pooled_scene_swap: Duration,
+}
+
+impl Default for LODTransitionCosts {
+    fn default() -> Self {
+        Self {
+            component_toggle: Duration::from_micros(2),
+            vat_swap: Duration::from_micros(10),
+            mesh_swap: Duration::from_micros(25),
+            scene_swap: Duration::from_micros(400),
+            pooled_scene_swap: Duration::from_micros(40),
+        }
+    }
+}
 
 // Animation LOD Strategy (similar to your current implementation)
 pub struct AnimationLODStrategy;
@@ -9,12 +48,25 @@ pub struct AnimationLODConfig {
     pub high_quality_distance: f32,
     pub medium_quality_distance: f32,
     pub low_quality_distance: f32,
+    pub disabled_mode: DisabledAnimationMode,
+    pub transition_costs: LODTransitionCosts,
+}
+
+/// What happens to an entity's animation at levels where it is disabled
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum DisabledAnimationMode {
+    /// Hold the current pose and resume in step when re-enabled
+    #[default]
+    Freeze,
+    /// Stop the animation, it restarts from the beginning when re-enabled
+    Stop,
 }
 
 #[derive(Component, Default)]
 pub struct AnimationLODData {
     pub animation_enabled: bool,
     pub update_rate: f32,
+    pub frozen: bool,
 }
 
 impl LODStrategy for AnimationLODStrategy {
@@ -26,7 +78,7 @@ impl LODStrategy for AnimationLODStrategy {
         entity: Entity,
         _from_level: u8,
         to_level: u8,
-        _config: &Self::Config,
+        config: &Self::Config,
         component_data: &mut Self::ComponentData,
     ) {
         match to_level {
@@ -48,6 +100,9 @@ impl LODStrategy for AnimationLODStrategy {
             }
         }
         
+        component_data.frozen = !component_data.animation_enabled
+            && config.disabled_mode == DisabledAnimationMode::Freeze;
+        
         if to_level < 3 {
             commands.entity(entity).insert(Visibility::Visible);
         }
@@ -61,6 +116,10 @@ impl LODStrategy for AnimationLODStrategy {
     ) {
         // Animation updates would be handled by the animation system
     }
+    
+    fn transition_cost(_from_level: u8, _to_level: u8, config: &Self::Config) -> Option<Duration> {
+        Some(config.transition_costs.component_toggle)
+    }
 }
 
 // Vertex Animation Texture (VAT) LOD Strategy
@@ -68,27 +127,57 @@ pub struct VATLODStrategy;
 
 #[derive(Resource)]
 pub struct VATLODConfig {
-    pub texture_handles: Vec<Handle<Image>>, // Different resolution VAT textures
+    pub meshes: Vec<Handle<Mesh>>,          // Mesh each VAT was baked from, the last one for later levels
+    pub materials: Vec<Handle<VATInstancedMaterial>>, // VAT played at each level, e.g. at different resolutions
     pub frame_counts: Vec<u32>,              // Number of frames in each VAT
+    pub clip_frame_counts: Vec<u32>,         // Frames of each baked clip, in place of the level's count for entities playing it
     pub playback_speeds: Vec<f32>,           // Playback speed for each LOD
+    pub fade_duration: f32,                  // Seconds representations cross-fade for, 0 switches instantly
+    pub transition_costs: LODTransitionCosts, // Charged against the LOD time budget
 }
 
 impl Default for VATLODConfig {
     fn default() -> Self {
         Self {
-            texture_handles: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
             frame_counts: vec![60, 30, 15, 1], // Example frame counts
+            clip_frame_counts: Vec::new(),
             playback_speeds: vec![1.0, 0.5, 0.25, 0.0],
+            fade_duration: 0.0,
+            transition_costs: LODTransitionCosts::default(),
         }
     }
 }
 
+impl VATLODConfig {
+    /// Frames `VATLODData` counts for clip `clip_index` at `level_index`.
+    /// Levels past the end of `frame_counts` use its last count.
+    pub fn frame_count(&self, level_index: usize, clip_index: usize) -> u32 {
+        self.clip_frame_counts.get(clip_index)
+            .or(self.frame_counts.get(level_index))
+            .or(self.frame_counts.last())
+            .copied()
+            .unwrap_or(1)
+    }
+
+    /// Playback speed at `level_index`, the last one for later levels
+    pub fn playback_speed(&self, level_index: usize) -> f32 {
+        self.playback_speeds.get(level_index)
+            .or(self.playback_speeds.last())
+            .copied()
+            .unwrap_or(1.0)
+    }
+}
+
 #[derive(Component)]
 pub struct VATLODData {
     pub current_frame: f32,
     pub frame_count: u32,
     pub playback_speed: f32,
     pub texture_index: usize,
+    pub speed_multiplier: f32, // Per-entity variation on top of `playback_speed`
+    pub clip_index: usize,     // Which baked clip to play
 }
 
 impl Default for VATLODData {
@@ -98,6 +187,8 @@ impl Default for VATLODData {
             frame_count: 60,
             playback_speed: 1.0,
             texture_index: 0,
+            speed_multiplier: 1.0,
+            clip_index: 0,
         }
     }
 }
@@ -116,15 +207,25 @@ impl LODStrategy for VATLODStrategy {
     ) {
         let level_index = to_level as usize;
         
-        if level_index < config.texture_handles.len() {
-            // Update VAT data
+        if level_index < config.materials.len() {
+            // Update VAT data, keeping the same point in the clip at the new frame count
+            let frame_count = config.frame_count(level_index, component_data.clip_index);
+            if component_data.frame_count > 0 {
+                component_data.current_frame *= frame_count as f32 / component_data.frame_count as f32;
+            }
             component_data.texture_index = level_index;
-            component_data.frame_count = config.frame_counts[level_index];
-            component_data.playback_speed = config.playback_speeds[level_index];
+            component_data.frame_count = frame_count;
+            component_data.playback_speed = config.playback_speed(level_index);
             
-            // Update material with new VAT texture
-            if let Some(texture) = config.texture_handles.get(level_index) {
-                commands.entity(entity).insert(VATTexture(texture.clone()));
+            // Show the level's VAT in place of the entity's scene
+            if let Some(mesh) = config.meshes.get(level_index).or(config.meshes.last()) {
+                commands.queue(SwapVATRepresentation {
+                    entity,
+                    mesh: mesh.clone(),
+                    material: config.materials[level_index].clone(),
+                    clip_index: component_data.clip_index,
+                    fade_duration: config.fade_duration,
+                });
             }
             
             // Show entity
@@ -142,7 +243,10 @@ impl LODStrategy for VATLODStrategy {
         component_data: &mut Self::ComponentData,
     ) {
         if component_data.playback_speed > 0.0 {
-            component_data.current_frame += time.delta_secs() * component_data.playback_speed * 30.0;
+            component_data.current_frame += time.delta_secs()
+                * component_data.playback_speed
+                * component_data.speed_multiplier
+                * 30.0;
             component_data.current_frame %= component_data.frame_count as f32;
         }
     }
@@ -150,6 +254,14 @@ impl LODStrategy for VATLODStrategy {
     fn requires_update() -> bool {
         true // VAT needs per-frame updates to advance animation
     }
+    
+    fn transition_cost(_from_level: u8, to_level: u8, config: &Self::Config) -> Option<Duration> {
+        if (to_level as usize) < config.materials.len() {
+            Some(config.transition_costs.vat_swap)
+        } else {
+            Some(config.transition_costs.component_toggle)
+        }
+    }
 }
 
 // Mesh Swap LOD Strategy - Enhanced to support both meshes and scenes
@@ -161,6 +273,12 @@ pub struct MeshSwapLODConfig {
     pub material_handles: Vec<Handle<StandardMaterial>>,
     // Add scene support for complete model swapping
     pub scene_handles: Vec<Handle<Scene>>,
+    // Swap scenes through the `ScenePool` instead of respawning them
+    pub pool_scenes: bool,
+    // Seconds levels cross-fade for, 0 switches instantly
+    pub fade_duration: f32,
+    // Charged against the LOD time budget
+    pub transition_costs: LODTransitionCosts,
 }
 
 impl Default for MeshSwapLODConfig {
@@ -169,6 +287,9 @@ impl Default for MeshSwapLODConfig {
             mesh_handles: Vec::new(),
             material_handles: Vec::new(),
             scene_handles: Vec::new(),
+            pool_scenes: false,
+            fade_duration: 0.0,
+            transition_costs: LODTransitionCosts::default(),
         }
     }
 }
@@ -199,7 +320,16 @@ impl LODStrategy for MeshSwapLODStrategy {
             
             // Swap scene
             if let Some(scene) = config.scene_handles.get(level_index) {
-                commands.entity(entity).insert(SceneRoot(scene.clone()));
+                if config.pool_scenes {
+                    commands.queue(SwapPooledScene {
+                        entity,
+                        scene: scene.clone(),
+                        fade_duration: config.fade_duration,
+                    });
+                } else {
+                    commands.queue(CrossFadeRepresentation { entity, fade_duration: config.fade_duration });
+                    commands.entity(entity).insert(SceneRoot(scene.clone()));
+                }
             }
             
             commands.entity(entity).insert(Visibility::Visible);
@@ -208,6 +338,8 @@ impl LODStrategy for MeshSwapLODStrategy {
         else if level_index < config.mesh_handles.len() {
             component_data.current_mesh_index = level_index;
             
+            commands.queue(CrossFadeRepresentation { entity, fade_duration: config.fade_duration });
+
             // Swap mesh
             if let Some(mesh) = config.mesh_handles.get(level_index) {
                 commands.entity(entity).insert(Mesh3d(mesh.clone()));
@@ -232,6 +364,19 @@ impl LODStrategy for MeshSwapLODStrategy {
     ) {
         // No per-frame updates needed for mesh swapping
     }
+    
+    fn transition_cost(_from_level: u8, to_level: u8, config: &Self::Config) -> Option<Duration> {
+        let level_index = to_level as usize;
+        if level_index < config.scene_handles.len() && config.pool_scenes {
+            Some(config.transition_costs.pooled_scene_swap)
+        } else if level_index < config.scene_handles.len() {
+            Some(config.transition_costs.scene_swap)
+        } else if level_index < config.mesh_handles.len() {
+            Some(config.transition_costs.mesh_swap)
+        } else {
+            Some(config.transition_costs.component_toggle)
+        }
+    }
 }
 
 // Hybrid LOD Strategy (combines multiple strategies)
@@ -261,6 +406,28 @@ pub enum LODStrategyType {
     MeshSwap,
 }
 
+impl HybridLODStrategy {
+    /// Which strategy handles `level`
+    fn strategy_for_level(level: u8, config: &HybridLODConfig) -> LODStrategyType {
+        if level == 0 {
+            // Level 0: Use animation with high-quality scene
+            LODStrategyType::Animation
+        } else if level == 1 {
+            // Level 1: Use animation with medium-quality scene (mesh swap)
+            LODStrategyType::MeshSwap
+        } else if level < config.use_vat_at_level {
+            // Level 2+: Use mesh swapping for different quality scenes
+            LODStrategyType::MeshSwap
+        } else if level < 3 {
+            // Optional VAT level (if use_vat_at_level is set low)
+            LODStrategyType::VAT
+        } else {
+            // Level 3+: Hidden/culled
+            LODStrategyType::MeshSwap
+        }
+    }
+}
+
 impl LODStrategy for HybridLODStrategy {
     type Config = HybridLODConfig;
     type ComponentData = HybridLODData;
@@ -274,32 +441,27 @@ impl LODStrategy for HybridLODStrategy {
         component_data: &mut Self::ComponentData,
     ) {
         // Determine which strategy to use based on LOD level
-        let new_strategy = if to_level == 0 {
-            // Level 0: Use animation with high-quality scene
-            LODStrategyType::Animation
-        } else if to_level == 1 {
-            // Level 1: Use animation with medium-quality scene (mesh swap)
-            LODStrategyType::MeshSwap
-        } else if to_level < config.use_vat_at_level {
-            // Level 2+: Use mesh swapping for different quality scenes
-            LODStrategyType::MeshSwap
-        } else if to_level < 3 {
-            // Optional VAT level (if use_vat_at_level is set low)
-            LODStrategyType::VAT
-        } else {
-            // Level 3+: Hidden/culled
-            LODStrategyType::MeshSwap
-        };
+        let new_strategy = Self::strategy_for_level(to_level, config);
         
         // Clean up previous strategy if switching
         if component_data.current_strategy != new_strategy {
+            // Queued before any scene swap so the old player is still there
+            if (component_data.current_strategy == LODStrategyType::VAT) != (new_strategy == LODStrategyType::VAT) {
+                commands.queue(MatchAnimationPhase {
+                    entity,
+                    to_vat: new_strategy == LODStrategyType::VAT,
+                });
+            }
             match component_data.current_strategy {
                 LODStrategyType::Animation => {
                     commands.entity(entity).remove::<AnimationPlayer>();
                     commands.entity(entity).remove::<AnimationTransitions>();
                 }
                 LODStrategyType::VAT => {
-                    commands.entity(entity).remove::<VATTexture>();
+                    commands.queue(RemoveVATRepresentation {
+                        entity,
+                        fade_duration: config.vat_config.fade_duration,
+                    });
                 }
                 _ => {}
             }
@@ -341,17 +503,15 @@ impl LODStrategy for HybridLODStrategy {
                     &mut component_data.mesh_swap_data,
                 );
                 
-                // Also apply animation strategy for levels that need it
-                if to_level <= 1 {
-                    AnimationLODStrategy::transition(
-                        commands,
-                        entity,
-                        from_level,
-                        to_level,
-                        &config.animation_config,
-                        &mut component_data.animation_data,
-                    );
-                }
+                // Also apply animation strategy so lower levels disable (or freeze) animation
+                AnimationLODStrategy::transition(
+                    commands,
+                    entity,
+                    from_level,
+                    to_level,
+                    &config.animation_config,
+                    &mut component_data.animation_data,
+                );
             }
             LODStrategyType::VAT => {
                 VATLODStrategy::transition(
@@ -383,27 +543,493 @@ impl LODStrategy for HybridLODStrategy {
     fn requires_update() -> bool {
         true // Because VAT might need updates
     }
+    
+    fn transition_cost(from_level: u8, to_level: u8, config: &Self::Config) -> Option<Duration> {
+        let animation_cost = || AnimationLODStrategy::transition_cost(from_level, to_level, &config.animation_config);
+        let mesh_swap_cost = || MeshSwapLODStrategy::transition_cost(from_level, to_level, &config.mesh_swap_config);
+        
+        let cost = match Self::strategy_for_level(to_level, config) {
+            LODStrategyType::Animation | LODStrategyType::MeshSwap => animation_cost()? + mesh_swap_cost()?,
+            LODStrategyType::VAT => VATLODStrategy::transition_cost(from_level, to_level, &config.vat_config)?,
+        };
+        Some(cost)
+    }
 }
 
-// Marker component for VAT texture
-#[derive(Component)]
-pub struct VATTexture(pub Handle<Image>);
+/// Lines up the VAT frame of a `HybridLODData` entity with its skeletal
+/// animation when it switches between the two, so neither restarts its clip
+struct MatchAnimationPhase {
+    entity: Entity,
+    to_vat: bool, // Otherwise the player seeks to the VAT frame
+}
+
+impl Command for MatchAnimationPhase {
+    fn apply(self, world: &mut World) {
+        let Some(vat_data) = world.get::<HybridLODData>(self.entity).map(|data| &data.vat_data) else {
+            return;
+        };
+        let frame_count = vat_data.frame_count.max(1) as f32;
+        let vat_phase = vat_data.current_frame / frame_count;
+
+        // A scene released for the VAT representation left its player's state
+        // in an `AnimationHandoff`
+        let scene = world.get::<PooledSceneInstance>(self.entity)
+            .map_or(self.entity, |instance| instance.0);
+        let live_player = find_animation_player(world, scene);
+        let (mut player, graph, behind) = if let Some(player) = live_player {
+            // Time the scheduler skipped is only applied on the player's next update
+            let behind = if world.get::<TimeSlicedAnimationPlayer>(player)
+                .is_some_and(TimeSlicedAnimationPlayer::has_paused)
+            {
+                world.get::<AnimationTimeSlice>(self.entity).map_or(0.0, |slice| slice.accumulated_time)
+            } else {
+                0.0
+            };
+            let Some(animation_player) = world.get::<AnimationPlayer>(player) else {
+                return;
+            };
+            (animation_player.clone(), world.get::<AnimationGraphHandle>(player).cloned(), behind)
+        } else if let Some(handoff) = world.get::<AnimationHandoff>(self.entity) {
+            // A handoff captured under another graph is stale, its node
+            // indices and capture time say nothing about the current clips
+            let current_graph = world.get::<AnimationGraphHandle>(self.entity);
+            if handoff.graph.as_ref().zip(current_graph).is_some_and(|(captured, current)| captured != current) {
+                return;
+            }
+            let now = world.resource::<Time>().elapsed_secs();
+            (handoff.player.clone(), handoff.graph.clone(), now - handoff.captured_at)
+        } else {
+            return;
+        };
+
+        let graphs = world.resource::<Assets<AnimationGraph>>();
+        let clips = world.resource::<Assets<AnimationClip>>();
+        let Some(graph) = graph.and_then(|handle| graphs.get(&handle)) else {
+            return;
+        };
+        let durations: Vec<(AnimationNodeIndex, f32)> = player.playing_animations()
+            .filter_map(|(node, _)| Some((*node, clip_duration(graph, clips, *node)?)))
+            .collect();
+
+        if self.to_vat {
+            // Phase of the most heavily weighted clip
+            let phase = durations.iter()
+                .filter_map(|(node, duration)| Some((player.animation(*node)?, duration)))
+                .max_by(|(a, _), (b, _)| a.weight().total_cmp(&b.weight()))
+                .map(|(animation, duration)| {
+                    let seek_time = animation.seek_time() + behind * animation.speed();
+                    (seek_time / duration).rem_euclid(1.0)
+                });
+            if let Some(phase) = phase
+                && let Some(mut data) = world.get_mut::<HybridLODData>(self.entity)
+            {
+                data.vat_data.current_frame = phase * frame_count;
+            }
+            return;
+        }
+
+        // Every playing clip picks up at the VAT's point in the loop
+        for (node, duration) in durations {
+            if let Some(animation) = player.animation_mut(node) {
+                let seek_time = vat_phase * duration - behind * animation.speed();
+                animation.seek_to(seek_time);
+            }
+        }
+        if let Some(live_player) = live_player {
+            if let Some(mut animation_player) = world.get_mut::<AnimationPlayer>(live_player) {
+                *animation_player = player;
+            }
+        } else if let Some(mut handoff) = world.get_mut::<AnimationHandoff>(self.entity) {
+            handoff.player = player;
+        }
+    }
+}
+
+/// The VAT mesh a `VATLODStrategy` shows under a LOD entity
+#[derive(Component, Debug)]
+pub struct VATRepresentation {
+    pub entity: Entity,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<VATInstancedMaterial>, // From the `VATLODConfig`, shared by the level
+}
+
+/// Shows `mesh` playing `material` under `entity` in place of its scene.
+///
+/// The scene (pooled or not) is released, its animation state kept as an
+/// `AnimationHandoff` for the scene that replaces the VAT. Every entity at the
+/// level shares `material` and is drawn instanced, playing its own frame and
+/// clip from the instance slot `VATInstancingPlugin` gives it.
+///
+/// With a `fade_duration` the new VAT mesh cross-fades with the scene or VAT
+/// mesh it replaces, which is released or despawned once it has faded out.
+pub struct SwapVATRepresentation {
+    pub entity: Entity,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<VATInstancedMaterial>,
+    pub clip_index: usize, // Started without blending in from another clip
+    pub fade_duration: f32,
+}
+
+impl Command for SwapVATRepresentation {
+    fn apply(self, world: &mut World) {
+        if world.get_entity(self.entity).is_err() {
+            return;
+        }
+        let current = world.get::<VATRepresentation>(self.entity);
+        if current.is_some_and(|current| current.mesh == self.mesh && current.material == self.material) {
+            return;
+        }
+        let current = current.map(|current| current.entity)
+            .filter(|representation| world.get_entity(*representation).is_ok());
+        let material = MeshMaterial3d(self.material.clone());
+        let fade = self.fade_duration > 0.0;
+
+        let representation = match current {
+            Some(representation) if !fade => {
+                world.entity_mut(representation).insert((Mesh3d(self.mesh.clone()), material));
+                representation
+            }
+            _ => {
+                // The new level's VAT carries on from the old one's clip and frame
+                let animation_state = current
+                    .and_then(|representation| world.get::<VATAnimationState>(representation))
+                    .cloned()
+                    .unwrap_or_else(|| VATAnimationState::default().with_clip(self.clip_index));
+                if let Some(representation) = current {
+                    fade_out_ghost(world, representation, self.fade_duration);
+                } else {
+                    release_scene(world, self.entity, self.fade_duration);
+                }
+
+                let representation = world.spawn((
+                    Mesh3d(self.mesh.clone()),
+                    material,
+                    animation_state,
+                    ChildOf(self.entity),
+                    Transform::IDENTITY,
+                    Visibility::Inherited,
+                )).id();
+                if fade && is_fading_out_under(world, self.entity) {
+                    world.entity_mut(representation).insert(LODFade::fade_in(self.fade_duration));
+                }
+                representation
+            }
+        };
+
+        world.entity_mut(self.entity).insert(VATRepresentation {
+            entity: representation,
+            mesh: self.mesh,
+            material: self.material,
+        });
+    }
+}
+
+/// Despawns the VAT mesh shown under `entity`, for the strategy taking over.
+/// With a `fade_duration` it fades out first.
+pub struct RemoveVATRepresentation {
+    pub entity: Entity,
+    pub fade_duration: f32,
+}
+
+impl Command for RemoveVATRepresentation {
+    fn apply(self, world: &mut World) {
+        let Ok(mut entity) = world.get_entity_mut(self.entity) else {
+            return;
+        };
+        let Some(representation) = entity.take::<VATRepresentation>() else {
+            return;
+        };
+        if world.get_entity(representation.entity).is_err() {
+            return;
+        }
+        if self.fade_duration > 0.0 {
+            fade_out_ghost(world, representation.entity, self.fade_duration);
+        } else {
+            world.entity_mut(representation.entity).despawn();
+        }
+    }
+}
 
 // Helper functions for setting up LOD levels
+// Exit distances add hysteresis around each boundary so entities sitting on
+// one don't flip between levels every frame
 pub fn create_standard_lod_levels() -> Vec<LODLevel> {
     vec![
-        LODLevel::new(0, 0.0, 10.0, 1.0 / 60.0),      // High quality
-        LODLevel::new(1, 10.0, 25.0, 1.0 / 30.0),     // Medium quality
-        LODLevel::new(2, 25.0, 50.0, 1.0 / 10.0),     // Low quality
-        LODLevel::new(3, 50.0, f32::MAX, 1.0),        // Culled/Static
+        LODLevel::new(0, 0.0, 10.0, 1.0 / 60.0)                          // High quality
+            .with_exit_distances(0.0, 11.0),
+        LODLevel::new(1, 10.0, 25.0, 1.0 / 30.0)                         // Medium quality
+            .with_exit_distances(9.0, 26.0),
+        LODLevel::new(2, 25.0, 50.0, 1.0 / 10.0)                         // Low quality
+            .with_exit_distances(24.0, 52.0),
+        LODLevel::new(3, 50.0, f32::MAX, 1.0)                            // Culled/Static
+            .with_exit_distances(48.0, f32::MAX),
     ]
 }
 
 pub fn create_aggressive_lod_levels() -> Vec<LODLevel> {
     vec![
-        LODLevel::new(0, 0.0, 5.0, 1.0 / 60.0),       // High quality
-        LODLevel::new(1, 5.0, 15.0, 1.0 / 20.0),      // Medium quality
-        LODLevel::new(2, 15.0, 30.0, 1.0 / 5.0),      // Low quality
-        LODLevel::new(3, 30.0, f32::MAX, 1.0),        // Culled/Static
+        LODLevel::new(0, 0.0, 5.0, 1.0 / 60.0)                           // High quality
+            .with_exit_distances(0.0, 5.5),
+        LODLevel::new(1, 5.0, 15.0, 1.0 / 20.0)                          // Medium quality
+            .with_exit_distances(4.5, 15.5),
+        LODLevel::new(2, 15.0, 30.0, 1.0 / 5.0)                          // Low quality
+            .with_exit_distances(14.5, 31.0),
+        LODLevel::new(3, 30.0, f32::MAX, 1.0)                            // Culled/Static
+            .with_exit_distances(29.0, f32::MAX),
     ]
 }
+
+/// Levels for `LODDistanceMetric::ScreenSize`, where the "distance" is the
+/// projected diameter in pixels, so level 0 covers the largest values
+pub fn create_screen_size_lod_levels() -> Vec<LODLevel> {
+    vec![
+        LODLevel::new(0, 150.0, f32::MAX, 1.0 / 60.0)                    // High quality
+            .with_exit_distances(140.0, f32::MAX),
+        LODLevel::new(1, 60.0, 150.0, 1.0 / 30.0)                        // Medium quality
+            .with_exit_distances(55.0, 160.0),
+        LODLevel::new(2, 15.0, 60.0, 1.0 / 10.0)                         // Low quality
+            .with_exit_distances(12.0, 65.0),
+        LODLevel::new(3, 0.0, 15.0, 1.0)                                 // Culled/Static
+            .with_exit_distances(0.0, 18.0),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::render::storage::ShaderStorageBuffer;
+    use crate::lod_system::fade::{LODFadeDirection, LODFadeGhost};
+    use crate::lod_system::vat::{VATAnimation, sync_vat_lod_playback};
+
+    const CLIP_DURATION: f32 = 2.0;
+
+    /// World with a manually advanced clock and one `CLIP_DURATION` clip
+    fn phase_world() -> (World, Handle<AnimationGraph>, AnimationNodeIndex) {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+
+        let mut clip = AnimationClip::default();
+        clip.set_duration(CLIP_DURATION);
+        let clip = world.get_resource_or_init::<Assets<AnimationClip>>().add(clip);
+        let (graph, node) = AnimationGraph::from_clip(clip);
+        let graph = world.get_resource_or_init::<Assets<AnimationGraph>>().add(graph);
+        (world, graph, node)
+    }
+
+    fn advance(world: &mut World, seconds: f32) {
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+    }
+
+    /// A rat whose scene was released at `seek_time` into the clip, now
+    /// showing a 60 frame VAT at `vat_frame`
+    fn spawn_released_rat(world: &mut World, graph: &Handle<AnimationGraph>, node: AnimationNodeIndex, seek_time: f32, vat_frame: f32) -> Entity {
+        let mut player = AnimationPlayer::default();
+        player.play(node).repeat().seek_to(seek_time);
+        let mut data = HybridLODData::default();
+        data.vat_data.frame_count = 60;
+        data.vat_data.current_frame = vat_frame;
+
+        let captured_at = world.resource::<Time>().elapsed_secs();
+        world.spawn((
+            data,
+            AnimationGraphHandle(graph.clone()),
+            AnimationHandoff {
+                player,
+                transitions: None,
+                graph: Some(AnimationGraphHandle(graph.clone())),
+                captured_at,
+            },
+        )).id()
+    }
+
+    fn vat_frame(world: &World, entity: Entity) -> f32 {
+        world.get::<HybridLODData>(entity).unwrap().vat_data.current_frame
+    }
+
+    fn handoff_seek_time(world: &World, entity: Entity, node: AnimationNodeIndex) -> f32 {
+        world.get::<AnimationHandoff>(entity).unwrap().player.animation(node).unwrap().seek_time()
+    }
+
+    #[test]
+    fn vat_picks_up_where_the_handoff_would_be_now() {
+        let (mut world, graph, node) = phase_world();
+        advance(&mut world, 1.0);
+        let rat = spawn_released_rat(&mut world, &graph, node, 0.5, 0.0);
+
+        // Half a second after the capture the clip is at 1.0 of 2.0 seconds
+        advance(&mut world, 0.5);
+        MatchAnimationPhase { entity: rat, to_vat: true }.apply(&mut world);
+        assert_eq!(vat_frame(&world, rat), 30.0);
+
+        // Reaching the end of the clip wraps the VAT frame
+        advance(&mut world, 1.0);
+        MatchAnimationPhase { entity: rat, to_vat: true }.apply(&mut world);
+        assert_eq!(vat_frame(&world, rat), 0.0);
+    }
+
+    #[test]
+    fn handoff_seeks_to_the_vat_frame_less_the_time_it_will_catch_up() {
+        let (mut world, graph, node) = phase_world();
+        advance(&mut world, 1.0);
+        let rat = spawn_released_rat(&mut world, &graph, node, 0.0, 45.0);
+
+        // The handoff is caught up by the time since its capture when it's
+        // applied, landing on the VAT's 75% through the clip
+        advance(&mut world, 0.25);
+        MatchAnimationPhase { entity: rat, to_vat: false }.apply(&mut world);
+        let seek_time = handoff_seek_time(&world, rat, node);
+        assert_eq!(seek_time, 0.75 * CLIP_DURATION - 0.25);
+    }
+
+    #[test]
+    fn handoffs_from_another_graph_are_ignored() {
+        let (mut world, graph, node) = phase_world();
+        let rat = spawn_released_rat(&mut world, &graph, node, 0.5, 12.0);
+        let other_graph = world.resource_mut::<Assets<AnimationGraph>>().add(AnimationGraph::new());
+        world.entity_mut(rat).insert(AnimationGraphHandle(other_graph));
+        advance(&mut world, 0.5);
+
+        MatchAnimationPhase { entity: rat, to_vat: true }.apply(&mut world);
+        assert_eq!(vat_frame(&world, rat), 12.0);
+        MatchAnimationPhase { entity: rat, to_vat: false }.apply(&mut world);
+        assert_eq!(handoff_seek_time(&world, rat, node), 0.5);
+    }
+
+    /// Two VAT rats at different points of a 60 frame clip, showing a level
+    /// through `SwapVATRepresentation`
+    fn vat_rats(world: &mut World, material: &Handle<VATInstancedMaterial>) -> [Entity; 2] {
+        let mesh = world.get_resource_or_init::<Assets<Mesh>>().add(Cuboid::default());
+        [15.0, 45.0].map(|current_frame| {
+            let rat = world.spawn(VATLODData { current_frame, ..default() }).id();
+            SwapVATRepresentation { entity: rat, mesh: mesh.clone(), material: material.clone(), clip_index: 0, fade_duration: 0.0 }
+                .apply(world);
+            rat
+        })
+    }
+
+    fn instanced_material(world: &mut World) -> Handle<VATInstancedMaterial> {
+        let mut buffers = Assets::<ShaderStorageBuffer>::default();
+        let mut material = VATInstancedMaterial::from_animation(Handle::default(), None, &mut buffers);
+        material.animation = None;
+        material.total_frames = 30.0;
+        world.insert_resource(buffers);
+        world.get_resource_or_init::<Assets<VATInstancedMaterial>>().add(material)
+    }
+
+    #[test]
+    fn vat_levels_count_the_frames_of_the_entitys_clip() {
+        let mut world = World::new();
+        let config = VATLODConfig {
+            materials: vec![instanced_material(&mut world)],
+            frame_counts: vec![60],
+            clip_frame_counts: vec![45, 90],
+            ..default()
+        };
+        let rat = world.spawn_empty().id();
+
+        // Halfway through the default 60 frames stays halfway through the clip
+        let mut data = VATLODData { clip_index: 1, current_frame: 30.0, ..default() };
+        VATLODStrategy::transition(&mut world.commands(), rat, 1, 0, &config, &mut data);
+        assert_eq!((data.frame_count, data.current_frame), (90, 45.0));
+
+        // Clips missing from the table fall back to the level's count
+        let mut data = VATLODData { clip_index: 2, ..default() };
+        VATLODStrategy::transition(&mut world.commands(), rat, 1, 0, &config, &mut data);
+        assert_eq!(data.frame_count, 60);
+        world.flush();
+    }
+
+    #[test]
+    fn vat_levels_past_the_per_level_tables_use_their_last_entry() {
+        let mut world = World::new();
+        let material = instanced_material(&mut world);
+        let config = VATLODConfig {
+            materials: vec![material.clone(), material.clone(), material],
+            frame_counts: vec![60],
+            playback_speeds: vec![1.0, 0.5],
+            ..default()
+        };
+        let rat = world.spawn_empty().id();
+
+        let mut data = VATLODData::default();
+        VATLODStrategy::transition(&mut world.commands(), rat, 0, 2, &config, &mut data);
+        assert_eq!((data.texture_index, data.frame_count, data.playback_speed), (2, 60, 0.5));
+
+        // Without any counts or speeds a level holds one frame at normal speed
+        let empty = VATLODConfig { frame_counts: Vec::new(), playback_speeds: Vec::new(), ..config };
+        VATLODStrategy::transition(&mut world.commands(), rat, 2, 1, &empty, &mut data);
+        assert_eq!((data.frame_count, data.playback_speed), (1, 1.0));
+        world.flush();
+    }
+
+    #[test]
+    fn vat_representations_share_the_level_material() {
+        let mut world = World::new();
+        let material = instanced_material(&mut world);
+        let rats = vat_rats(&mut world, &material);
+
+        for rat in rats {
+            let representation = world.get::<VATRepresentation>(rat).unwrap().entity;
+            let shown = world.get::<MeshMaterial3d<VATInstancedMaterial>>(representation).unwrap();
+            assert_eq!(shown.0, material);
+        }
+        assert_eq!(world.resource::<Assets<VATInstancedMaterial>>().len(), 1);
+    }
+
+    #[test]
+    fn vat_representations_play_their_own_frame() {
+        let mut world = World::new();
+        world.init_resource::<Assets<VATAnimation>>();
+        let material = instanced_material(&mut world);
+        let rats = vat_rats(&mut world, &material);
+
+        world.run_system_once(sync_vat_lod_playback::<VATLODData, VATInstancedMaterial>).unwrap();
+
+        // The LOD data's 60 frames are mapped onto the material's 30
+        let frames = rats.map(|rat| {
+            let representation = world.get::<VATRepresentation>(rat).unwrap().entity;
+            world.get::<VATAnimationState>(representation).unwrap().current_frame
+        });
+        assert_eq!(frames, [7.5, 22.5]);
+    }
+
+    #[test]
+    fn swapping_vat_levels_fades_the_old_level_out() {
+        let mut world = World::new();
+        let material = instanced_material(&mut world);
+        let [rat, _] = vat_rats(&mut world, &material);
+        let old = world.get::<VATRepresentation>(rat).unwrap().entity;
+        world.get_mut::<VATAnimationState>(old).unwrap().current_frame = 12.0;
+
+        let low_detail = world.resource_mut::<Assets<Mesh>>().add(Sphere::default());
+        SwapVATRepresentation { entity: rat, mesh: low_detail, material, clip_index: 0, fade_duration: 0.5 }
+            .apply(&mut world);
+
+        let new = world.get::<VATRepresentation>(rat).unwrap().entity;
+        assert_ne!(new, old);
+        assert!(world.get::<LODFadeGhost>(old).is_some());
+        assert_eq!(world.get::<LODFade>(old).unwrap().direction, LODFadeDirection::Out);
+        assert_eq!(world.get::<LODFade>(new).unwrap().direction, LODFadeDirection::In);
+        // Picks up the old level's playback
+        assert_eq!(world.get::<VATAnimationState>(new).unwrap().current_frame, 12.0);
+    }
+
+    #[test]
+    fn removing_a_vat_representation_fades_it_out() {
+        let mut world = World::new();
+        let material = instanced_material(&mut world);
+        let [faded, removed] = vat_rats(&mut world, &material);
+        let faded_representation = world.get::<VATRepresentation>(faded).unwrap().entity;
+        let removed_representation = world.get::<VATRepresentation>(removed).unwrap().entity;
+
+        RemoveVATRepresentation { entity: faded, fade_duration: 0.5 }.apply(&mut world);
+        RemoveVATRepresentation { entity: removed, fade_duration: 0.0 }.apply(&mut world);
+
+        assert!(world.get::<VATRepresentation>(faded).is_none());
+        assert!(world.get::<LODFadeGhost>(faded_representation).is_some());
+        assert!(is_fading_out_under(&world, faded));
+        assert!(world.get_entity(removed_representation).is_err());
+    }
+}
+
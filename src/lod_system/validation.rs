@@ -0,0 +1,408 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::platform::collections::HashMap;
+use bevy::render::render_resource::{ShaderDefVal, ShaderImport, ShaderType, Source};
+use naga_oil::compose::{
+    ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderDefValue, ShaderLanguage,
+};
+
+use crate::lod_system::bake::headless_app;
+use crate::lod_system::vat::VATMaterialUniform;
+use crate::lod_system::vat_instancing::VATInstancedMaterialUniform;
+
+// Headless validation of the LOD shaders, for CI machines without a GPU
+
+const VAT_SHADER_PATH: &str = "shaders/vat_shader.wgsl";
+const LOD_DITHER_LIBRARY_PATH: &str = "shaders/lod_dither.wgsl";
+const LOD_DITHER_MATERIAL_PATH: &str = "shaders/lod_dither_material.wgsl";
+const LOD_DITHER_PREPASS_PATH: &str = "shaders/lod_dither_prepass.wgsl";
+const SHADER_LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Shader defs the pipeline cache and mesh pipeline add to every variant
+const GLOBAL_SHADER_DEFS: &[(&str, u32)] = &[
+    ("MAX_DIRECTIONAL_LIGHTS", 10),
+    ("MAX_CASCADES_PER_LIGHT", 4),
+    ("AVAILABLE_STORAGE_BUFFER_BINDINGS", 8),
+];
+
+const MAIN_PASS_DEFS: &[&str] = &["VERTEX_UVS", "VERTEX_UVS_A", "VERTEX_NORMALS", "VERTEX_OUTPUT_INSTANCE_INDEX"];
+// Materials are masked while they fade, so the shadow pass runs their fragment
+const SHADOW_PASS_DEFS: &[&str] = &["PREPASS_PIPELINE", "VERTEX_OUTPUT_INSTANCE_INDEX", "MAY_DISCARD"];
+// Directional light shadows on GPUs without depth clip control
+const EMULATED_DEPTH_SHADOW_PASS_DEFS: &[&str] = &[
+    "PREPASS_PIPELINE", "VERTEX_OUTPUT_INSTANCE_INDEX", "MAY_DISCARD",
+    "UNCLIPPED_DEPTH_ORTHO_EMULATION", "PREPASS_FRAGMENT",
+];
+const PREPASS_DEFS: &[&str] = &[
+    "PREPASS_PIPELINE", "VERTEX_UVS", "VERTEX_UVS_A", "VERTEX_NORMALS",
+    "VERTEX_OUTPUT_INSTANCE_INDEX", "DEPTH_PREPASS", "NORMAL_PREPASS",
+    "NORMAL_PREPASS_OR_DEFERRED_PREPASS", "MOTION_VECTOR_PREPASS",
+    "UNCLIPPED_DEPTH_ORTHO_EMULATION", "PREPASS_FRAGMENT", "MAY_DISCARD",
+];
+
+struct ShaderVariant {
+    name: &'static str,
+    defs: &'static [&'static str],
+    // Added by the material's `specialize`
+    material_defs: &'static [&'static str],
+    bindings: fn() -> Vec<(u32, MaterialBinding)>,
+}
+
+/// Variants bevy specialises `VATMaterial` and `VATInstancedMaterial` into for
+/// the rat meshes
+const VAT_SHADER_VARIANTS: &[ShaderVariant] = &[
+    ShaderVariant { name: "main pass", defs: MAIN_PASS_DEFS, material_defs: &[], bindings: vat_material_bindings },
+    ShaderVariant { name: "shadow pass", defs: SHADOW_PASS_DEFS, material_defs: &[], bindings: vat_material_bindings },
+    ShaderVariant {
+        name: "emulated depth shadow pass",
+        defs: EMULATED_DEPTH_SHADOW_PASS_DEFS,
+        material_defs: &[],
+        bindings: vat_material_bindings,
+    },
+    ShaderVariant { name: "normal and motion vector prepass", defs: PREPASS_DEFS, material_defs: &[], bindings: vat_material_bindings },
+    ShaderVariant {
+        name: "instanced main pass",
+        defs: MAIN_PASS_DEFS,
+        material_defs: &["VAT_INSTANCED"],
+        bindings: vat_instanced_material_bindings,
+    },
+    ShaderVariant {
+        name: "instanced shadow pass",
+        defs: SHADOW_PASS_DEFS,
+        material_defs: &["VAT_INSTANCED"],
+        bindings: vat_instanced_material_bindings,
+    },
+    ShaderVariant {
+        name: "instanced emulated depth shadow pass",
+        defs: EMULATED_DEPTH_SHADOW_PASS_DEFS,
+        material_defs: &["VAT_INSTANCED"],
+        bindings: vat_instanced_material_bindings,
+    },
+    ShaderVariant {
+        name: "instanced normal and motion vector prepass",
+        defs: PREPASS_DEFS,
+        material_defs: &["VAT_INSTANCED"],
+        bindings: vat_instanced_material_bindings,
+    },
+    ShaderVariant {
+        name: "rest delta octahedral main pass",
+        defs: MAIN_PASS_DEFS,
+        material_defs: VAT_QUANTISED_DEFS,
+        bindings: vat_material_bindings,
+    },
+    ShaderVariant {
+        name: "rest delta octahedral prepass",
+        defs: PREPASS_DEFS,
+        material_defs: VAT_QUANTISED_DEFS,
+        bindings: vat_material_bindings,
+    },
+    ShaderVariant {
+        name: "instanced rest delta octahedral main pass",
+        defs: MAIN_PASS_DEFS,
+        material_defs: &["VAT_INSTANCED", "VAT_REST_DELTA", "VAT_NORMALS_OCTAHEDRAL"],
+        bindings: vat_instanced_material_bindings,
+    },
+];
+
+/// Passes `LODDitherMaterial` is drawn in, by the shader drawing it
+const LOD_DITHER_SHADER_VARIANTS: &[(&str, &str, &[&str])] = &[
+    ("dither main pass", LOD_DITHER_MATERIAL_PATH, MAIN_PASS_DEFS),
+    ("dither shadow pass", LOD_DITHER_PREPASS_PATH, SHADOW_PASS_DEFS),
+    ("dither emulated depth shadow pass", LOD_DITHER_PREPASS_PATH, EMULATED_DEPTH_SHADOW_PASS_DEFS),
+    ("dither normal and motion vector prepass", LOD_DITHER_PREPASS_PATH, PREPASS_DEFS),
+];
+
+/// `VATEncoding::shader_defs` of every encoding option
+const VAT_QUANTISED_DEFS: &[&str] = &["VAT_REST_DELTA", "VAT_NORMALS_OCTAHEDRAL"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MaterialBinding {
+    Uniform { size: u32 },
+    Texture2d,
+    Sampler,
+    ReadOnlyStorage,
+}
+
+impl MaterialBinding {
+    /// A `#[uniform]` of `T`, padded like WGSL pads the shader's struct
+    fn uniform<T: ShaderType>() -> Self {
+        Self::Uniform { size: T::min_size().get() as u32 }
+    }
+}
+
+/// `VATMaterial`'s `AsBindGroup` layout, in the material bind group
+fn vat_material_bindings() -> Vec<(u32, MaterialBinding)> {
+    vec![
+        (0, MaterialBinding::uniform::<VATMaterialUniform>()),
+        (1, MaterialBinding::Texture2d),
+        (2, MaterialBinding::Texture2d),
+        (3, MaterialBinding::Sampler),
+        (4, MaterialBinding::Sampler),
+        (5, MaterialBinding::Texture2d),
+        (6, MaterialBinding::Sampler),
+    ]
+}
+
+/// `VATInstancedMaterial`'s `AsBindGroup` layout, in the material bind group
+fn vat_instanced_material_bindings() -> Vec<(u32, MaterialBinding)> {
+    let mut bindings = vat_material_bindings();
+    bindings[0].1 = MaterialBinding::uniform::<VATInstancedMaterialUniform>();
+    bindings.push((7, MaterialBinding::ReadOnlyStorage));
+    bindings
+}
+const MATERIAL_BIND_GROUP: u32 = 2;
+
+#[derive(Debug)]
+pub enum ShaderValidationError {
+    Load(String),
+    Timeout,
+    Compose { variant: &'static str, message: String },
+    Validate { variant: &'static str, message: String },
+    Binding { variant: &'static str, message: String },
+}
+
+impl fmt::Display for ShaderValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(path) => write!(f, "failed to load {path}"),
+            Self::Timeout => write!(f, "shaders didn't load within {}s", SHADER_LOAD_TIMEOUT.as_secs()),
+            Self::Compose { variant, message } => write!(f, "{variant}: {message}"),
+            Self::Validate { variant, message } => write!(f, "{variant}: {message}"),
+            Self::Binding { variant, message } => write!(f, "{variant}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderValidationError {}
+
+/// Composes `vat_shader.wgsl` against the shader libraries of `bevy_pbr` for
+/// every variant in `VAT_SHADER_VARIANTS`, validates it with naga and checks
+/// its material bindings match `VATMaterial` or `VATInstancedMaterial`. Needs no GPU. Returns the names
+/// of the validated variants.
+pub fn validate_vat_shader() -> Result<Vec<&'static str>, ShaderValidationError> {
+    let (app, handles) = load_shaders(&[VAT_SHADER_PATH])?;
+    let shaders = app.world().resource::<Assets<Shader>>();
+    let libraries = shader_libraries(shaders);
+    let Some(vat_shader) = shaders.get(&handles[0]) else {
+        return Err(ShaderValidationError::Load(VAT_SHADER_PATH.to_string()));
+    };
+
+    VAT_SHADER_VARIANTS.iter()
+        .map(|variant| validate_variant(vat_shader, &libraries, variant).map(|_| variant.name))
+        .collect()
+}
+
+/// Composes and validates the shaders of `LODDitherMaterial` for every pass
+/// in `LOD_DITHER_SHADER_VARIANTS`, like `validate_vat_shader`. Returns the
+/// names of the validated variants.
+pub fn validate_lod_dither_shaders() -> Result<Vec<&'static str>, ShaderValidationError> {
+    let paths = [LOD_DITHER_MATERIAL_PATH, LOD_DITHER_PREPASS_PATH];
+    let (app, handles) = load_shaders(&paths)?;
+    let shaders = app.world().resource::<Assets<Shader>>();
+    let libraries = shader_libraries(shaders);
+
+    LOD_DITHER_SHADER_VARIANTS.iter()
+        .map(|&(variant, path, defs)| {
+            let handle = paths.iter().position(|loaded| *loaded == path).map(|index| &handles[index]);
+            let Some(shader) = handle.and_then(|handle| shaders.get(handle)) else {
+                return Err(ShaderValidationError::Load(path.to_string()));
+            };
+            compose_variant(shader, path, &libraries, variant, defs.iter()).map(|_| variant)
+        })
+        .collect()
+}
+
+/// Loads `paths` and the `lod_system::dither` library they import in a
+/// headless app
+fn load_shaders(paths: &[&'static str]) -> Result<(App, Vec<Handle<Shader>>), ShaderValidationError> {
+    let mut app = headless_app(|_| {});
+    let asset_server = app.world().resource::<AssetServer>().clone();
+    // Normally loaded by `LODFadePlugin` or `VATMaterialPlugin`
+    let library: Handle<Shader> = asset_server.load(LOD_DITHER_LIBRARY_PATH);
+    let handles: Vec<Handle<Shader>> = paths.iter().map(|path| asset_server.load(*path)).collect();
+
+    let started = Instant::now();
+    let loading = || std::iter::once((&library, LOD_DITHER_LIBRARY_PATH)).chain(handles.iter().zip(paths.iter().copied()));
+    while !loading().all(|(handle, _)| asset_server.is_loaded(handle)) {
+        for (handle, path) in loading() {
+            if asset_server.load_state(handle).is_failed() {
+                return Err(ShaderValidationError::Load(path.to_string()));
+            }
+        }
+        if started.elapsed() > SHADER_LOAD_TIMEOUT {
+            return Err(ShaderValidationError::Timeout);
+        }
+        app.update();
+    }
+    Ok((app, handles))
+}
+
+/// Every loaded shader library, by import path
+fn shader_libraries(shaders: &Assets<Shader>) -> HashMap<String, &Shader> {
+    shaders.iter()
+        .filter_map(|(_, shader)| match &shader.import_path {
+            ShaderImport::Custom(name) => Some((name.clone(), shader)),
+            ShaderImport::AssetPath(_) => None,
+        })
+        .collect()
+}
+
+fn validate_variant(
+    shader: &Shader,
+    libraries: &HashMap<String, &Shader>,
+    shader_variant: &ShaderVariant,
+) -> Result<(), ShaderValidationError> {
+    let variant = shader_variant.name;
+    let defs = shader_variant.defs.iter().chain(shader_variant.material_defs);
+    let module = compose_variant(shader, VAT_SHADER_PATH, libraries, variant, defs)?;
+
+    check_material_bindings(&module, &(shader_variant.bindings)())
+        .map_err(|message| ShaderValidationError::Binding { variant, message })
+}
+
+/// Composes `shader` with `defs` and validates it with naga
+fn compose_variant<'a>(
+    shader: &Shader,
+    path: &str,
+    libraries: &HashMap<String, &Shader>,
+    variant: &'static str,
+    defs: impl Iterator<Item = &'a &'a str>,
+) -> Result<naga::Module, ShaderValidationError> {
+    let Source::Wgsl(source) = &shader.source else {
+        return Err(ShaderValidationError::Load(path.to_string()));
+    };
+
+    let mut shader_defs: std::collections::HashMap<String, ShaderDefValue> = GLOBAL_SHADER_DEFS.iter()
+        .map(|(name, value)| (name.to_string(), ShaderDefValue::UInt(*value)))
+        .collect();
+    shader_defs.extend(defs.map(|name| (name.to_string(), ShaderDefValue::Bool(true))));
+
+    // Like the pipeline cache, with the capabilities every backend has
+    let mut composer = Composer::default();
+    for import in &shader.imports {
+        add_library(&mut composer, libraries, &import.module_name())
+            .map_err(|message| ShaderValidationError::Compose { variant, message })?;
+    }
+    let module = composer
+        .make_naga_module(NagaModuleDescriptor {
+            source,
+            file_path: path,
+            shader_defs,
+            ..default()
+        })
+        .map_err(|error| ShaderValidationError::Compose { variant, message: error.emit_to_string(&composer) })?;
+
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::default())
+        .validate(&module)
+        .map_err(|error| ShaderValidationError::Validate { variant, message: error.emit_to_string(source) })?;
+    Ok(module)
+}
+
+/// Adds the shader library `name` and everything it imports to `composer`
+fn add_library(composer: &mut Composer, libraries: &HashMap<String, &Shader>, name: &str) -> Result<(), String> {
+    if composer.contains_module(name) {
+        return Ok(());
+    }
+    let Some(library) = libraries.get(name) else {
+        return Err(format!("no shader library defines {name}"));
+    };
+    for import in &library.imports {
+        add_library(composer, libraries, &import.module_name())?;
+    }
+
+    let Source::Wgsl(source) = &library.source else {
+        return Err(format!("{name} isn't WGSL"));
+    };
+    let shader_defs = library.shader_defs.iter()
+        .map(|def| match def {
+            ShaderDefVal::Bool(name, value) => (name.clone(), ShaderDefValue::Bool(*value)),
+            ShaderDefVal::Int(name, value) => (name.clone(), ShaderDefValue::Int(*value)),
+            ShaderDefVal::UInt(name, value) => (name.clone(), ShaderDefValue::UInt(*value)),
+        })
+        .collect();
+    composer
+        .add_composable_module(ComposableModuleDescriptor {
+            source,
+            file_path: &library.path,
+            language: ShaderLanguage::Wgsl,
+            additional_imports: &library.additional_imports,
+            shader_defs,
+            ..default()
+        })
+        .map(|_| ())
+        .map_err(|error| error.emit_to_string(composer))
+}
+
+/// Every material binding the shader declares must be in the material's
+/// layout, with the same type
+fn check_material_bindings(module: &naga::Module, bindings: &[(u32, MaterialBinding)]) -> Result<(), String> {
+    for (_, global) in module.global_variables.iter() {
+        let Some(binding) = &global.binding else {
+            continue;
+        };
+        if binding.group != MATERIAL_BIND_GROUP {
+            continue;
+        }
+        let name = global.name.as_deref().unwrap_or("?");
+        let declared = match &module.types[global.ty].inner {
+            naga::TypeInner::Struct { span, .. } if global.space == naga::AddressSpace::Uniform => {
+                MaterialBinding::Uniform { size: *span }
+            }
+            naga::TypeInner::Image { dim: naga::ImageDimension::D2, arrayed: false, class: naga::ImageClass::Sampled { kind: naga::ScalarKind::Float, multi: false } } => {
+                MaterialBinding::Texture2d
+            }
+            naga::TypeInner::Sampler { comparison: false } => MaterialBinding::Sampler,
+            naga::TypeInner::Array { .. } if global.space == (naga::AddressSpace::Storage { access: naga::StorageAccess::LOAD }) => {
+                MaterialBinding::ReadOnlyStorage
+            }
+            other => return Err(format!("{name} at binding {} has unsupported type {other:?}", binding.binding)),
+        };
+
+        let expected = bindings.iter()
+            .find(|(index, _)| *index == binding.binding)
+            .map(|(_, expected)| *expected);
+        match expected {
+            Some(expected) if expected == declared => {}
+            Some(expected) => {
+                return Err(format!("{name} at binding {} is {declared:?}, the material has {expected:?}", binding.binding));
+            }
+            None => return Err(format!("{name} at binding {} isn't in the material", binding.binding)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vat_shader_validates_in_every_variant() {
+        let validated = validate_vat_shader().unwrap_or_else(|error| panic!("{error}"));
+        let expected: Vec<_> = VAT_SHADER_VARIANTS.iter().map(|variant| variant.name).collect();
+        assert_eq!(validated, expected);
+    }
+
+    #[test]
+    fn lod_dither_shaders_validate_in_every_pass() {
+        let validated = validate_lod_dither_shaders().unwrap_or_else(|error| panic!("{error}"));
+        let expected: Vec<_> = LOD_DITHER_SHADER_VARIANTS.iter().map(|(variant, ..)| *variant).collect();
+        assert_eq!(validated, expected);
+    }
+
+    #[test]
+    fn uniforms_of_the_wrong_size_are_rejected() {
+        let (app, handles) = load_shaders(&[VAT_SHADER_PATH]).unwrap();
+        let shaders = app.world().resource::<Assets<Shader>>();
+        let libraries = shader_libraries(shaders);
+        let shader = shaders.get(&handles[0]).unwrap();
+        let instanced = compose_variant(shader, VAT_SHADER_PATH, &libraries, "instanced", MAIN_PASS_DEFS.iter().chain(&["VAT_INSTANCED"])).unwrap();
+        check_material_bindings(&instanced, &vat_instanced_material_bindings()).unwrap();
+
+        // The instanced shader's `VATUniforms` is smaller than `VATMaterial`'s
+        let error = check_material_bindings(&instanced, &vat_material_bindings()).unwrap_err();
+        assert!(error.starts_with("vat at binding 0 is Uniform"), "{error}");
+    }
+}
@@ -1,28 +1,51 @@
+use std::fmt;
+
 use bevy::{
     prelude::*,
-    render::render_resource::{AsBindGroup, ShaderRef},
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_asset::RenderAssetUsages,
+        render_resource::{
+            AsBindGroup, Extent3d, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+            TextureDimension, TextureFormat,
+        },
+    },
     reflect::TypePath,
-    asset::Asset,
+    asset::{Asset, AssetLoader, LoadContext, io::Reader},
+    image::{ImageLoaderSettings, ImageSampler},
 };
+use serde::{Deserialize, Serialize};
+
+use crate::lod_system::fade::{dithered_alpha_mode, load_dither_shader};
+use crate::lod_system::strategies::{HybridLODData, VATLODData, VATRepresentation};
+
+/// Playback rate of VATs without a `VATAnimation`
+const DEFAULT_VAT_FPS: f32 = 30.0;
 
 /// Material for Vertex Animation Textures
 #[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
+#[bind_group_data(VATEncoding)]
+#[uniform(0, VATMaterialUniform)]
 pub struct VATMaterial {
     // Animation parameters
-    #[uniform(0)]
     pub current_frame: f32,
-    #[uniform(0)]
     pub total_frames: f32,
-    #[uniform(0)]
     pub vertex_count: u32,
-    #[uniform(0)]
     pub texture_width: u32,
     
     // Bounding box for position reconstruction
-    #[uniform(0)]
     pub bbox_min: Vec3,
-    #[uniform(0)]
     pub bbox_max: Vec3,
+
+    // Frame range of the playing clip, `current_frame` counts from its start
+    pub clip_start: u32,
+    pub clip_frame_count: u32,
+    // The clip being blended out, weighted by `1 - clip_blend`
+    pub previous_clip_start: u32,
+    pub previous_clip_frame_count: u32,
+    pub previous_frame: f32,
+    pub clip_blend: f32,
     
     // VAT textures
     #[texture(1)]
@@ -40,6 +63,148 @@ pub struct VATMaterial {
     
     // Control parameters
     pub alpha_mode: AlphaMode,
+
+    // How the textures are decoded, a shader def rather than a uniform
+    pub encoding: VATEncoding,
+    
+    // Fills in the fields above once loaded, see `VATMaterial::from_animation`
+    pub animation: Option<Handle<VATAnimation>>,
+}
+
+impl VATMaterial {
+    /// Material for a `.vat` file. Frame counts, vertex count, bounding box and
+    /// textures are taken from the `VATAnimation` once it has loaded.
+    pub fn from_animation(animation: Handle<VATAnimation>, base_color_texture: Option<Handle<Image>>) -> Self {
+        Self {
+            current_frame: 0.0,
+            total_frames: 1.0,
+            vertex_count: 0,
+            texture_width: 1,
+            bbox_min: Vec3::ZERO,
+            bbox_max: Vec3::ZERO,
+            clip_start: 0,
+            clip_frame_count: 1,
+            previous_clip_start: 0,
+            previous_clip_frame_count: 1,
+            previous_frame: 0.0,
+            clip_blend: 1.0,
+            position_texture: Handle::default(),
+            normal_texture: Handle::default(),
+            base_color_texture,
+            alpha_mode: AlphaMode::Opaque,
+            encoding: VATEncoding::default(),
+            animation: Some(animation),
+        }
+    }
+}
+
+pub use shader_types::VATMaterialUniform;
+
+// The field checks `ShaderType` derives are never called and trip `dead_code`
+#[allow(dead_code)]
+mod shader_types {
+    use bevy::prelude::Vec3;
+    use bevy::render::render_resource::ShaderType;
+
+    /// `VATMaterial` at binding 0, `VATUniforms` in `vat_shader.wgsl`
+    #[derive(ShaderType, Clone, Default)]
+    pub struct VATMaterialUniform {
+        pub current_frame: f32,
+        pub total_frames: f32,
+        pub vertex_count: u32,
+        pub texture_width: u32,
+        pub bbox_min: Vec3,
+        pub bbox_max: Vec3,
+        pub clip_start: u32,
+        pub clip_frame_count: u32,
+        pub previous_clip_start: u32,
+        pub previous_clip_frame_count: u32,
+        pub previous_frame: f32,
+        pub clip_blend: f32,
+    }
+}
+
+impl From<&VATMaterial> for VATMaterialUniform {
+    fn from(material: &VATMaterial) -> Self {
+        Self {
+            current_frame: material.current_frame,
+            total_frames: material.total_frames,
+            vertex_count: material.vertex_count,
+            texture_width: material.texture_width,
+            bbox_min: material.bbox_min,
+            bbox_max: material.bbox_max,
+            clip_start: material.clip_start,
+            clip_frame_count: material.clip_frame_count,
+            previous_clip_start: material.previous_clip_start,
+            previous_clip_frame_count: material.previous_clip_frame_count,
+            previous_frame: material.previous_frame,
+            clip_blend: material.clip_blend,
+        }
+    }
+}
+
+/// Materials playing baked VAT textures, see `VATMaterial` and
+/// `VATInstancedMaterial`
+pub trait VATAnimatedMaterial: Material {
+    fn animation(&self) -> Option<&Handle<VATAnimation>>;
+
+    /// Frames in the textures
+    fn total_frames(&self) -> u32;
+
+    /// Copies the metadata and textures of a loaded `VATAnimation`
+    fn apply_animation(&mut self, animation: &VATAnimation);
+
+    /// Start and frame count of clip `clip_index` of the material's
+    /// `VATAnimation`, or the whole texture without one. A clip past the end
+    /// of a clip table is warned about, e.g. a VAT baked with fewer clips
+    /// than the entities pick from.
+    fn clip_range(&self, animations: &Assets<VATAnimation>, clip_index: usize) -> (u32, u32) {
+        let whole = (0, self.total_frames().max(1));
+        let Some(animation) = self.animation().and_then(|animation| animations.get(animation)) else {
+            return whole;
+        };
+        if let Some(clip) = animation.clips.get(clip_index) {
+            return (clip.start_frame, clip.frame_count);
+        }
+        if !animation.clips.is_empty() {
+            warn_once!(
+                "VAT clip {clip_index} is past the {} clips of the animation, playing the whole texture",
+                animation.clips.len(),
+            );
+        }
+        whole
+    }
+
+    fn frames_per_second(&self, animations: &Assets<VATAnimation>) -> f32 {
+        self.animation()
+            .and_then(|animation| animations.get(animation))
+            .map_or(DEFAULT_VAT_FPS, |animation| animation.frames_per_second)
+    }
+}
+
+impl VATAnimatedMaterial for VATMaterial {
+    fn animation(&self) -> Option<&Handle<VATAnimation>> {
+        self.animation.as_ref()
+    }
+
+    fn total_frames(&self) -> u32 {
+        self.total_frames as u32
+    }
+
+    // Plays the whole texture until a clip is picked with `VATAnimationState`
+    fn apply_animation(&mut self, animation: &VATAnimation) {
+        self.total_frames = animation.frame_count as f32;
+        self.clip_start = 0;
+        self.clip_frame_count = animation.frame_count;
+        self.clip_blend = 1.0;
+        self.vertex_count = animation.vertex_count;
+        self.texture_width = animation.texture_width;
+        self.bbox_min = animation.bbox_min;
+        self.bbox_max = animation.bbox_max;
+        self.position_texture = animation.positions.clone();
+        self.normal_texture = animation.normals.clone().unwrap_or_default();
+        self.encoding = animation.encoding;
+    }
 }
 
 impl Material for VATMaterial {
@@ -50,19 +215,56 @@ impl Material for VATMaterial {
     fn vertex_shader() -> ShaderRef {
         "shaders/vat_shader.wgsl".into()
     }
+
+    // Depth, normals and shadows follow the animation
+    fn prepass_vertex_shader() -> ShaderRef {
+        "shaders/vat_shader.wgsl".into()
+    }
+
+    // Dithers LOD fades in the prepass and shadow pass
+    fn prepass_fragment_shader() -> ShaderRef {
+        "shaders/vat_shader.wgsl".into()
+    }
     
+    // Any instance may be fading, so the material is always masked
     fn alpha_mode(&self) -> AlphaMode {
-        self.alpha_mode
+        dithered_alpha_mode(self.alpha_mode)
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_vat_encoding(descriptor, &key.bind_group_data);
+        Ok(())
     }
 }
 
-/// Component to track VAT animation state
-#[derive(Component)]
+/// Component to track VAT animation state.
+///
+/// Changing `clip_index` starts that clip of the material's `VATAnimation`
+/// from its first frame, cross-fading from the previous clip over
+/// `clip_blend_time` seconds.
+#[derive(Component, Clone)]
 pub struct VATAnimationState {
-    pub current_frame: f32,
+    pub current_frame: f32,    // Within the playing clip
     pub playback_speed: f32,
     pub loop_animation: bool,
     pub is_playing: bool,
+    pub clip_index: usize,     // Into the `VATAnimation`'s clips
+    pub clip_blend_time: f32,
+    playing_clip: usize,
+    previous_clip: Option<VATClipBlend>,
+}
+
+/// A clip still playing while it is blended out
+#[derive(Clone, Copy, Debug)]
+struct VATClipBlend {
+    clip_index: usize,
+    frame: f32,
+    elapsed: f32,
 }
 
 impl Default for VATAnimationState {
@@ -72,6 +274,32 @@ impl Default for VATAnimationState {
             playback_speed: 1.0,
             loop_animation: true,
             is_playing: true,
+            clip_index: 0,
+            clip_blend_time: 0.2,
+            playing_clip: 0,
+            previous_clip: None,
+        }
+    }
+}
+
+impl VATAnimationState {
+    pub fn with_frame(mut self, frame: f32) -> Self {
+        self.current_frame = frame;
+        self
+    }
+
+    pub fn with_clip(mut self, clip_index: usize) -> Self {
+        self.clip_index = clip_index;
+        self.playing_clip = clip_index;
+        self
+    }
+
+    /// Weight of the playing clip against the one blending out, 1 once the
+    /// blend is over
+    pub fn clip_blend(&self) -> f32 {
+        match self.previous_clip {
+            Some(previous) if self.clip_blend_time > 0.0 => (previous.elapsed / self.clip_blend_time).min(1.0),
+            _ => 1.0,
         }
     }
 }
@@ -102,53 +330,166 @@ impl Default for VATBundle {
     }
 }
 
-/// System to update VAT materials with current animation frame
+/// System to update VAT materials with current animation frame and clips
 pub fn update_vat_materials(
-    _time: Res<Time>,
     mut materials: ResMut<Assets<VATMaterial>>,
+    animations: Res<Assets<VATAnimation>>,
     query: Query<(&MeshMaterial3d<VATMaterial>, &VATAnimationState), Changed<VATAnimationState>>,
 ) {
     for (mesh_material, animation_state) in query.iter() {
         if let Some(material) = materials.get_mut(&mesh_material.0) {
-            material.current_frame = animation_state.current_frame;
+            let playback = animation_state.playback(material, &animations);
+            material.current_frame = playback.current_frame;
+            material.clip_start = playback.clip_start;
+            material.clip_frame_count = playback.clip_frame_count;
+            material.previous_frame = playback.previous_frame;
+            material.previous_clip_start = playback.previous_clip_start;
+            material.previous_clip_frame_count = playback.previous_clip_frame_count;
+            material.clip_blend = playback.clip_blend;
         }
     }
 }
 
-/// System to advance VAT animations
-pub fn advance_vat_animations(
+/// System to advance VAT animations, starting clips picked with
+/// `VATAnimationState::clip_index`
+pub fn advance_vat_animations<M: VATAnimatedMaterial>(
     time: Res<Time>,
-    mut query: Query<(&mut VATAnimationState, &MeshMaterial3d<VATMaterial>)>,
-    materials: Res<Assets<VATMaterial>>,
+    mut query: Query<(&mut VATAnimationState, &MeshMaterial3d<M>)>,
+    materials: Res<Assets<M>>,
+    animations: Res<Assets<VATAnimation>>,
 ) {
     for (mut animation_state, mesh_material) in query.iter_mut() {
+        let Some(material) = materials.get(&mesh_material.0) else {
+            continue;
+        };
+        let animation_state = animation_state.as_mut();
+
+        if animation_state.clip_index != animation_state.playing_clip {
+            animation_state.previous_clip = (animation_state.clip_blend_time > 0.0).then_some(VATClipBlend {
+                clip_index: animation_state.playing_clip,
+                frame: animation_state.current_frame,
+                elapsed: 0.0,
+            });
+            animation_state.playing_clip = animation_state.clip_index;
+            animation_state.current_frame = 0.0;
+        }
         if !animation_state.is_playing {
             continue;
         }
-        
-        if let Some(material) = materials.get(&mesh_material.0) {
-            let delta = time.delta_secs() * animation_state.playback_speed * 30.0; // Assuming 30 fps base
-            animation_state.current_frame += delta;
-            
+
+        let delta = time.delta_secs() * animation_state.playback_speed * material.frames_per_second(&animations);
+        let advance = |frame: f32, clip_index: usize| {
+            let (_, frame_count) = material.clip_range(&animations, clip_index);
             if animation_state.loop_animation {
-                animation_state.current_frame %= material.total_frames;
+                (frame + delta) % frame_count as f32
             } else {
-                animation_state.current_frame = animation_state.current_frame.min(material.total_frames - 1.0);
+                (frame + delta).min(frame_count as f32 - 1.0)
             }
+        };
+        animation_state.current_frame = advance(animation_state.current_frame, animation_state.playing_clip);
+
+        if let Some(mut previous) = animation_state.previous_clip {
+            previous.frame = advance(previous.frame, previous.clip_index);
+            previous.elapsed += time.delta_secs();
+            animation_state.previous_clip = (previous.elapsed < animation_state.clip_blend_time).then_some(previous);
         }
     }
 }
 
+impl VATAnimationState {
+    /// Frame, clips and blend of the state as the shader plays them
+    pub fn playback<M: VATAnimatedMaterial>(&self, material: &M, animations: &Assets<VATAnimation>) -> VATPlayback {
+        let (clip_start, clip_frame_count) = material.clip_range(animations, self.playing_clip);
+        let mut playback = VATPlayback {
+            current_frame: self.current_frame,
+            clip_start,
+            clip_frame_count,
+            previous_frame: 0.0,
+            previous_clip_start: clip_start,
+            previous_clip_frame_count: clip_frame_count,
+            clip_blend: self.clip_blend(),
+        };
+        if let Some(previous) = self.previous_clip {
+            (playback.previous_clip_start, playback.previous_clip_frame_count) = material.clip_range(animations, previous.clip_index);
+            playback.previous_frame = previous.frame;
+        }
+        playback
+    }
+}
+
+/// What a VAT entity shows this frame: a frame of the playing clip, blended
+/// with a frame of the previous clip by `clip_blend`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VATPlayback {
+    pub current_frame: f32,
+    pub clip_start: u32,
+    pub clip_frame_count: u32,
+    pub previous_frame: f32,
+    pub previous_clip_start: u32,
+    pub previous_clip_frame_count: u32,
+    pub clip_blend: f32,
+}
+
+/// LOD data that carries a `VATLODData`
+pub trait VATLODSource: Component {
+    fn vat_data(&self) -> &VATLODData;
+}
+
+impl VATLODSource for VATLODData {
+    fn vat_data(&self) -> &VATLODData {
+        self
+    }
+}
+
+impl VATLODSource for HybridLODData {
+    fn vat_data(&self) -> &VATLODData {
+        &self.vat_data
+    }
+}
+
+/// Plays each `VATRepresentation` at the frame and clip of its entity's
+/// `VATLODData`, which keeps advancing while the entity isn't at a VAT level
+pub(crate) fn sync_vat_lod_playback<D: VATLODSource, M: VATAnimatedMaterial>(
+    owners: Query<(&D, &VATRepresentation)>,
+    mut representations: Query<(&mut VATAnimationState, &MeshMaterial3d<M>)>,
+    materials: Res<Assets<M>>,
+    animations: Res<Assets<VATAnimation>>,
+) {
+    for (owner, representation) in owners.iter() {
+        let Ok((mut animation_state, mesh_material)) = representations.get_mut(representation.entity) else {
+            continue;
+        };
+        let Some(material) = materials.get(&mesh_material.0) else {
+            continue;
+        };
+        let lod_data = owner.vat_data();
+
+        // The LOD data counts its own frames, play the same point of the clip
+        let (_, clip_frame_count) = material.clip_range(&animations, lod_data.clip_index);
+        let phase = lod_data.current_frame / lod_data.frame_count.max(1) as f32;
+        animation_state.clip_index = lod_data.clip_index;
+        animation_state.current_frame = phase * clip_frame_count as f32;
+        // Only advances a clip still blending out
+        animation_state.playback_speed = lod_data.playback_speed * lod_data.speed_multiplier;
+    }
+}
+
 /// Plugin to add VAT material support
 pub struct VATMaterialPlugin;
 
 impl Plugin for VATMaterialPlugin {
     fn build(&self, app: &mut App) {
+        // The VAT shader dithers `LODFade`s
+        load_dither_shader(app);
+
         app.add_plugins(MaterialPlugin::<VATMaterial>::default())
+            .init_asset::<VATAnimation>()
+            .init_asset_loader::<VATAnimationLoader>()
             .add_systems(Update, (
-                advance_vat_animations,
+                apply_vat_animations::<VATMaterial>,
+                advance_vat_animations::<VATMaterial>,
                 update_vat_materials,
-            ));
+            ).chain());
     }
 }
 
@@ -170,21 +511,29 @@ pub fn create_vat_material(
         texture_width,
         bbox_min,
         bbox_max,
+        clip_start: 0,
+        clip_frame_count: total_frames.max(1),
+        previous_clip_start: 0,
+        previous_clip_frame_count: 1,
+        previous_frame: 0.0,
+        clip_blend: 1.0,
         position_texture,
         normal_texture,
         base_color_texture,
         alpha_mode: AlphaMode::Opaque,
+        encoding: VATEncoding::default(),
+        animation: None,
     }
 }
 
 /// Configuration for VAT texture generation
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct VATConfig {
     pub texture_width: u32,
     pub frames_per_second: f32,
     pub total_frames: u32,
     pub include_normals: bool,
-    pub compression_quality: f32, // 0.0 to 1.0
+    pub encoding: VATEncoding,
 }
 
 impl Default for VATConfig {
@@ -194,7 +543,103 @@ impl Default for VATConfig {
             frames_per_second: 30.0,
             total_frames: 60,
             include_normals: true,
-            compression_quality: 0.9,
+            encoding: VATEncoding::default(),
+        }
+    }
+}
+
+/// How positions are quantised in a VAT's position texture
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VATPositionEncoding {
+    /// RGBA16 unorm, normalised to the bounding box
+    #[default]
+    Unorm16,
+    /// RGBA8 unorm, normalised to the bounding box
+    Unorm8,
+}
+
+/// How normals are quantised in a VAT's normal texture
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VATNormalEncoding {
+    /// RGBA16 unorm, `n * 0.5 + 0.5`
+    #[default]
+    Unorm16,
+    /// RG8 unorm, octahedral mapped (see `octahedral_encode`)
+    Octahedral8,
+}
+
+/// Texture encodings of a VAT, trading VRAM against accuracy.
+///
+/// With `rest_delta` the position texture stores each vertex's offset from its
+/// rest position in the mesh, which spans a much smaller box than the
+/// positions themselves and so loses less to quantisation. The VAT must then
+/// be drawn on the mesh it was baked from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VATEncoding {
+    pub positions: VATPositionEncoding,
+    pub normals: VATNormalEncoding,
+    pub rest_delta: bool,
+}
+
+impl VATEncoding {
+    /// Bytes per texel of the position and normal textures on the GPU
+    pub fn texel_sizes(&self) -> (u32, u32) {
+        let positions = match self.positions {
+            VATPositionEncoding::Unorm16 => 8,
+            VATPositionEncoding::Unorm8 => 4,
+        };
+        let normals = match self.normals {
+            VATNormalEncoding::Unorm16 => 8,
+            VATNormalEncoding::Octahedral8 => 2,
+        };
+        (positions, normals)
+    }
+
+    /// Defs `vat_shader.wgsl` decodes the encoding with
+    pub fn shader_defs(&self) -> impl Iterator<Item = &'static str> {
+        [
+            self.rest_delta.then_some("VAT_REST_DELTA"),
+            (self.normals == VATNormalEncoding::Octahedral8).then_some("VAT_NORMALS_OCTAHEDRAL"),
+        ].into_iter().flatten()
+    }
+}
+
+/// Maps a unit normal onto the `0..1` square, folding the lower hemisphere
+/// over the upper one's diagonals
+pub fn octahedral_encode(normal: Vec3) -> Vec2 {
+    let normal = normal / (normal.x.abs() + normal.y.abs() + normal.z.abs()).max(f32::EPSILON);
+    let folded = if normal.z >= 0.0 {
+        normal.xy()
+    } else {
+        (Vec2::ONE - normal.yx().abs()) * normal.xy().signum()
+    };
+    folded * 0.5 + 0.5
+}
+
+/// Inverse of `octahedral_encode`, like `vat_shader.wgsl`
+pub fn octahedral_decode(encoded: Vec2) -> Vec3 {
+    let encoded = encoded * 2.0 - 1.0;
+    let mut normal = encoded.extend(1.0 - encoded.x.abs() - encoded.y.abs());
+    let fold = (-normal.z).max(0.0);
+    normal.x += if normal.x >= 0.0 { -fold } else { fold };
+    normal.y += if normal.y >= 0.0 { -fold } else { fold };
+    normal.normalize_or_zero()
+}
+
+/// Puts a VAT's encoding in the pipeline key, see `specialize_vat_encoding`
+impl From<&VATMaterial> for VATEncoding {
+    fn from(material: &VATMaterial) -> Self {
+        material.encoding
+    }
+}
+
+/// Adds the shader defs of the material's `VATEncoding` to both stages
+pub(crate) fn specialize_vat_encoding(descriptor: &mut RenderPipelineDescriptor, encoding: &VATEncoding) {
+    for def in encoding.shader_defs() {
+        descriptor.vertex.shader_defs.push(def.into());
+        if let Some(fragment) = &mut descriptor.fragment {
+            fragment.shader_defs.push(def.into());
         }
     }
 }
@@ -213,6 +658,95 @@ pub fn calculate_vat_texture_dimensions(vertex_count: usize, frame_count: usize)
     (width, height)
 }
 
+/// A vertex as `vat_shader.wgsl` decodes it, in model space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VATVertexSample {
+    pub position: Vec3,
+    pub normal: Vec3,
+}
+
+/// The frames bracketing `current_frame` and the blend between them, wrapping
+/// from the last frame to the first (and from before the first frame to the
+/// last) like the shader
+pub fn vat_frame_blend(current_frame: f32, frame_count: u32) -> (u32, u32, f32) {
+    let frame_count = frame_count.max(1);
+    let frame = current_frame.rem_euclid(frame_count as f32);
+    let frame_a = frame as u32 % frame_count;
+    let frame_b = (frame_a + 1) % frame_count;
+    (frame_a, frame_b, frame.fract())
+}
+
+/// Texel of `vertex` in `frame`, see `VATFile` for the layout
+pub fn vat_texel(vertex: u32, frame: u32, vertex_count: u32, texture_width: u32) -> UVec2 {
+    let rows_per_frame = vertex_count.div_ceil(texture_width);
+    UVec2::new(vertex % texture_width, frame * rows_per_frame + vertex / texture_width)
+}
+
+/// Blends two decoded frames like the shader: positions are lerped, normals
+/// normalised-lerped, keeping the first if the two cancel out
+pub fn blend_vat_samples(a: VATVertexSample, b: VATVertexSample, blend: f32) -> VATVertexSample {
+    let normal = a.normal.lerp(b.normal, blend);
+    VATVertexSample {
+        position: a.position.lerp(b.position, blend),
+        normal: if normal.length_squared() > 1e-8 { normal.normalize() } else { a.normal },
+    }
+}
+
+/// Decodes one texel of each VAT texture like `vat_shader.wgsl`, given the
+/// vertex's rest position for `rest_delta` encodings
+pub fn decode_vat_texel(
+    encoding: &VATEncoding,
+    bbox_min: Vec3,
+    bbox_max: Vec3,
+    rest_position: Vec3,
+    position: Vec3,
+    normal: Option<Vec3>,
+) -> VATVertexSample {
+    let mut decoded = bbox_min + position * (bbox_max - bbox_min);
+    if encoding.rest_delta {
+        decoded += rest_position;
+    }
+    // Without normals the shader reads the default white texture
+    let normal = normal.unwrap_or(Vec3::ONE);
+    let normal = match encoding.normals {
+        VATNormalEncoding::Unorm16 => (normal * 2.0 - 1.0).normalize_or_zero(),
+        VATNormalEncoding::Octahedral8 => octahedral_decode(normal.xy()),
+    };
+    VATVertexSample { position: decoded, normal }
+}
+
+/// CPU reference of `vat_shader.wgsl`: decodes `vertex` of a loaded
+/// `VATAnimation` at `current_frame` of `clip`, or of the whole texture
+/// without one. `rest_position` is the vertex in the mesh, only read for
+/// `rest_delta` encodings. `None` if its textures aren't loaded.
+pub fn sample_vat_vertex(
+    animation: &VATAnimation,
+    images: &Assets<Image>,
+    clip: Option<&VATClip>,
+    vertex: u32,
+    rest_position: Vec3,
+    current_frame: f32,
+) -> Option<VATVertexSample> {
+    let positions = images.get(&animation.positions)?;
+    let normals = animation.normals.as_ref().and_then(|normals| images.get(normals));
+
+    let decode = |frame: u32| -> Option<VATVertexSample> {
+        let texel = vat_texel(vertex, frame, animation.vertex_count, animation.texture_width);
+        let position = Vec3::from_slice(&positions.get_color_at(texel.x, texel.y).ok()?.to_linear().to_f32_array());
+        let normal = match normals {
+            Some(normals) => Some(Vec3::from_slice(&normals.get_color_at(texel.x, texel.y).ok()?.to_linear().to_f32_array())),
+            None => None,
+        };
+        Some(decode_vat_texel(
+            &animation.encoding, animation.bbox_min, animation.bbox_max, rest_position, position, normal,
+        ))
+    };
+
+    let (clip_start, clip_frame_count) = clip.map_or((0, animation.frame_count), |clip| (clip.start_frame, clip.frame_count));
+    let (frame_a, frame_b, blend) = vat_frame_blend(current_frame, clip_frame_count);
+    Some(blend_vat_samples(decode(clip_start + frame_a)?, decode(clip_start + frame_b)?, blend))
+}
+
 /// Component to mark entities that should use simplified VAT (static frame)
 #[derive(Component)]
 pub struct SimplifiedVAT {
@@ -224,3 +758,550 @@ impl Default for SimplifiedVAT {
         Self { static_frame: 0 }
     }
 }
+
+/// A named frame range of a `VATAnimation`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VATClip {
+    pub name: String,
+    pub start_frame: u32,
+    pub frame_count: u32,
+}
+
+/// Contents of a `.vat` file, a RON header describing baked VAT textures.
+///
+/// Frame `f` of vertex `v` is the texel at `(v % texture_width,
+/// f * rows_per_frame + v / texture_width)` of `positions` and `normals`.
+/// Positions are stored normalised to `bbox_min..bbox_max`, as offsets from
+/// the rest pose with `encoding.rest_delta`. Normals are `n * 0.5 + 0.5`, or
+/// octahedral in the luminance and alpha of an 8-bit PNG with
+/// `VATNormalEncoding::Octahedral8`. Texture paths are relative to the `.vat`
+/// file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VATFile {
+    pub vertex_count: u32,
+    pub frame_count: u32,
+    pub frames_per_second: f32,
+    pub texture_width: u32,
+    pub rows_per_frame: u32,
+    pub bbox_min: [f32; 3],
+    pub bbox_max: [f32; 3],
+    pub positions: String,
+    pub normals: Option<String>,
+    pub mesh: Option<String>,       // glTF label of the baked primitive, e.g. "Mesh0/Primitive0"
+    pub clips: Vec<VATClip>,
+    #[serde(default)]
+    pub encoding: VATEncoding,
+}
+
+impl VATFile {
+    pub fn texture_height(&self) -> u32 {
+        self.rows_per_frame * self.frame_count
+    }
+
+    /// Checks the header is consistent, so a bad file fails to load instead
+    /// of rendering garbage
+    pub fn validate(&self) -> Result<(), VATAnimationLoaderError> {
+        let invalid = |reason: String| Err(VATAnimationLoaderError::Invalid(reason));
+        if self.vertex_count == 0 || self.frame_count == 0 || self.texture_width == 0 {
+            return invalid("vertex_count, frame_count and texture_width must be non-zero".to_string());
+        }
+        if self.rows_per_frame != self.vertex_count.div_ceil(self.texture_width) {
+            return invalid(format!(
+                "rows_per_frame is {}, {} vertices in rows of {} need {}",
+                self.rows_per_frame, self.vertex_count, self.texture_width,
+                self.vertex_count.div_ceil(self.texture_width),
+            ));
+        }
+        if (0..3).any(|axis| self.bbox_min[axis] > self.bbox_max[axis]) {
+            return invalid("bbox_min is above bbox_max".to_string());
+        }
+        if self.encoding.rest_delta && self.mesh.is_none() {
+            return invalid("rest_delta positions need the mesh they were baked from".to_string());
+        }
+        // Only the `Unorm16` decode of the default white texture is a usable normal
+        if self.normals.is_none() && self.encoding.normals != VATNormalEncoding::Unorm16 {
+            return invalid(format!("{:?} normals need a normals texture", self.encoding.normals));
+        }
+        for clip in &self.clips {
+            if clip.frame_count == 0 || clip.start_frame + clip.frame_count > self.frame_count {
+                return invalid(format!(
+                    "clip {} covers frames {}..{} of {}",
+                    clip.name, clip.start_frame, clip.start_frame + clip.frame_count, self.frame_count,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Baked VAT textures and everything needed to play them back, loaded from a
+/// `.vat` file
+#[derive(Asset, TypePath, Debug)]
+pub struct VATAnimation {
+    pub vertex_count: u32,
+    pub frame_count: u32,
+    pub frames_per_second: f32,
+    pub texture_width: u32,
+    pub rows_per_frame: u32,
+    pub bbox_min: Vec3,
+    pub bbox_max: Vec3,
+    pub positions: Handle<Image>,
+    pub normals: Option<Handle<Image>>,
+    pub mesh: Option<String>,
+    pub clips: Vec<VATClip>,
+    pub encoding: VATEncoding,
+}
+
+impl VATAnimation {
+    pub fn clip(&self, name: &str) -> Option<&VATClip> {
+        self.clips.iter().find(|clip| clip.name == name)
+    }
+}
+
+#[derive(Debug)]
+pub enum VATAnimationLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Invalid(String),
+    Texture { path: String, reason: String },
+}
+
+impl fmt::Display for VATAnimationLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Ron(error) => write!(f, "{error}"),
+            Self::Invalid(reason) => write!(f, "invalid .vat file: {reason}"),
+            Self::Texture { path, reason } => write!(f, "failed to load VAT texture {path}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for VATAnimationLoaderError {}
+
+impl From<std::io::Error> for VATAnimationLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for VATAnimationLoaderError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Ron(error)
+    }
+}
+
+/// Loads `.vat` files, along with their textures as linear, unfiltered images
+#[derive(Default)]
+pub struct VATAnimationLoader;
+
+impl AssetLoader for VATAnimationLoader {
+    type Asset = VATAnimation;
+    type Settings = ();
+    type Error = VATAnimationLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<VATAnimation, VATAnimationLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: VATFile = ron::de::from_bytes(&bytes)?;
+        file.validate()?;
+
+        let mut load_texture = |path: &str| {
+            let path = load_context.asset_path()
+                .resolve_embed(path)
+                .map_err(|error| VATAnimationLoaderError::Invalid(error.to_string()))?;
+            Ok::<_, VATAnimationLoaderError>(load_context.loader()
+                .with_settings(|settings: &mut ImageLoaderSettings| {
+                    // Texels are data, not colours
+                    settings.is_srgb = false;
+                    settings.sampler = ImageSampler::nearest();
+                })
+                .load(path))
+        };
+        let positions = load_texture(&file.positions)?;
+        let normals = match (file.normals.as_deref(), file.encoding.normals) {
+            (Some(path), VATNormalEncoding::Octahedral8) => Some(load_octahedral_normals(load_context, path).await?),
+            (Some(path), VATNormalEncoding::Unorm16) => Some(load_texture(path)?),
+            (None, _) => None,
+        };
+
+        Ok(VATAnimation {
+            vertex_count: file.vertex_count,
+            frame_count: file.frame_count,
+            frames_per_second: file.frames_per_second,
+            texture_width: file.texture_width,
+            rows_per_frame: file.rows_per_frame,
+            bbox_min: Vec3::from(file.bbox_min),
+            bbox_max: Vec3::from(file.bbox_max),
+            positions,
+            normals,
+            mesh: file.mesh,
+            clips: file.clips,
+            encoding: file.encoding,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["vat"]
+    }
+}
+
+/// Loads octahedral normals as an RG8 texture, the image loader would expand
+/// the two-channel PNG to RGBA8
+async fn load_octahedral_normals(
+    load_context: &mut LoadContext<'_>,
+    path: &str,
+) -> Result<Handle<Image>, VATAnimationLoaderError> {
+    let path = load_context.asset_path()
+        .resolve_embed(path)
+        .map_err(|error| VATAnimationLoaderError::Invalid(error.to_string()))?;
+    let texture_error = |reason: String| VATAnimationLoaderError::Texture { path: path.to_string(), reason };
+    let bytes = load_context.read_asset_bytes(&path).await
+        .map_err(|error| texture_error(error.to_string()))?;
+    let decoded = image::load_from_memory(&bytes)
+        .map_err(|error| texture_error(error.to_string()))?
+        .into_luma_alpha8();
+
+    let mut normals = Image::new(
+        Extent3d { width: decoded.width(), height: decoded.height(), depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        decoded.into_raw(),
+        TextureFormat::Rg8Unorm,
+        RenderAssetUsages::default(),
+    );
+    normals.sampler = ImageSampler::nearest();
+    Ok(load_context.add_labeled_asset("normals".to_string(), normals))
+}
+
+/// Fills in VAT materials built with `from_animation` once their
+/// `VATAnimation` has loaded, or when it is reloaded
+pub(crate) fn apply_vat_animations<M: VATAnimatedMaterial>(
+    mut animation_events: EventReader<AssetEvent<VATAnimation>>,
+    mut material_events: EventReader<AssetEvent<M>>,
+    animations: Res<Assets<VATAnimation>>,
+    images: Res<Assets<Image>>,
+    mut materials: ResMut<Assets<M>>,
+) {
+    let mut changed_animations = Vec::new();
+    for event in animation_events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            changed_animations.push(*id);
+        }
+    }
+    let added_materials: Vec<_> = material_events.read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if changed_animations.is_empty() && added_materials.is_empty() {
+        return;
+    }
+
+    for (id, material) in materials.iter_mut() {
+        let Some(handle) = material.animation() else {
+            continue;
+        };
+        if !changed_animations.contains(&handle.id()) && !added_materials.contains(&id) {
+            continue;
+        }
+        let Some(animation) = animations.get(handle) else {
+            continue;
+        };
+
+        // A texture that doesn't match the header would be sampled out of step
+        if let Some(positions) = images.get(&animation.positions)
+            && (positions.width() != animation.texture_width
+                || positions.height() != animation.rows_per_frame * animation.frame_count)
+        {
+            warn!(
+                "VAT positions are {}x{}, expected {}x{}",
+                positions.width(), positions.height(),
+                animation.texture_width, animation.rows_per_frame * animation.frame_count,
+            );
+        }
+        material.apply_animation(animation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(position: Vec3, normal: Vec3) -> VATVertexSample {
+        VATVertexSample { position, normal }
+    }
+
+    #[test]
+    fn frame_blend_wraps_to_the_first_frame() {
+        assert_eq!(vat_frame_blend(2.25, 4), (2, 3, 0.25));
+        assert_eq!(vat_frame_blend(3.5, 4), (3, 0, 0.5));
+        assert_eq!(vat_frame_blend(4.0, 4), (0, 1, 0.0));
+        assert_eq!(vat_frame_blend(9.75, 4), (1, 2, 0.75));
+    }
+
+    #[test]
+    fn frame_blend_wraps_before_the_first_frame_to_the_last() {
+        assert_eq!(vat_frame_blend(-0.25, 4), (3, 0, 0.75));
+        assert_eq!(vat_frame_blend(-4.0, 4), (0, 1, 0.0));
+    }
+
+    #[test]
+    fn frame_blend_of_a_single_or_empty_clip_holds_its_frame() {
+        assert_eq!(vat_frame_blend(0.5, 1), (0, 0, 0.5));
+        assert_eq!(vat_frame_blend(0.5, 0), (0, 0, 0.5));
+    }
+
+    #[test]
+    fn texels_start_each_frame_on_a_new_row() {
+        // 5 vertices in rows of 4 take 2 rows a frame
+        assert_eq!(vat_texel(0, 0, 5, 4), UVec2::new(0, 0));
+        assert_eq!(vat_texel(4, 0, 5, 4), UVec2::new(0, 1));
+        assert_eq!(vat_texel(3, 1, 5, 4), UVec2::new(3, 2));
+        assert_eq!(vat_texel(4, 2, 5, 4), UVec2::new(0, 5));
+    }
+
+    #[test]
+    fn blending_lerps_positions_and_normalises_normals() {
+        let a = sample(Vec3::ZERO, Vec3::X);
+        let b = sample(Vec3::new(2.0, 4.0, 0.0), Vec3::Y);
+
+        assert_eq!(blend_vat_samples(a, b, 0.0), a);
+        assert_eq!(blend_vat_samples(a, b, 1.0), b);
+        let halfway = blend_vat_samples(a, b, 0.5);
+        assert_eq!(halfway.position, Vec3::new(1.0, 2.0, 0.0));
+        assert!(halfway.normal.abs_diff_eq(Vec3::new(1.0, 1.0, 0.0).normalize(), 1e-6));
+    }
+
+    #[test]
+    fn blending_opposite_normals_keeps_the_first() {
+        let a = sample(Vec3::ZERO, Vec3::Z);
+        let b = sample(Vec3::ONE, -Vec3::Z);
+        assert_eq!(blend_vat_samples(a, b, 0.5).normal, Vec3::Z);
+    }
+
+    #[test]
+    fn texels_decode_into_the_bounding_box() {
+        let bbox_min = Vec3::new(-1.0, 0.0, 2.0);
+        let bbox_max = Vec3::new(1.0, 4.0, 6.0);
+        let rest = Vec3::new(10.0, 20.0, 30.0);
+
+        let decoded = decode_vat_texel(&VATEncoding::default(), bbox_min, bbox_max, rest, Vec3::splat(0.5), Some(Vec3::new(0.5, 1.0, 0.5)));
+        assert_eq!(decoded.position, Vec3::new(0.0, 2.0, 4.0));
+        assert_eq!(decoded.normal, Vec3::Y);
+
+        let rest_delta = VATEncoding { rest_delta: true, ..default() };
+        let decoded = decode_vat_texel(&rest_delta, bbox_min, bbox_max, rest, Vec3::splat(0.5), None);
+        assert_eq!(decoded.position, Vec3::new(10.0, 22.0, 34.0));
+        // Missing normals read as the default white texture
+        assert!(decoded.normal.abs_diff_eq(Vec3::ONE.normalize(), 1e-6));
+    }
+
+    #[test]
+    fn octahedral_normals_round_trip() {
+        let octahedral = VATEncoding { normals: VATNormalEncoding::Octahedral8, ..default() };
+        for normal in [Vec3::X, -Vec3::Y, Vec3::Z, -Vec3::Z, Vec3::new(0.3, -0.5, -0.8).normalize()] {
+            let encoded = octahedral_encode(normal).extend(0.0);
+            let decoded = decode_vat_texel(&octahedral, Vec3::ZERO, Vec3::ONE, Vec3::ZERO, Vec3::ZERO, Some(encoded));
+            assert!(decoded.normal.abs_diff_eq(normal, 1e-5), "{normal} decoded as {}", decoded.normal);
+        }
+    }
+
+    /// One vertex over 4 frames, at x = frame in a 0..4 bounding box. The
+    /// first two frames are clip "a", the last two clip "b".
+    fn four_frame_animation(images: &mut Assets<Image>) -> VATAnimation {
+        let texels: Vec<f32> = (0..4).flat_map(|frame| [frame as f32 / 4.0, 0.0, 0.0, 1.0]).collect();
+        let positions = images.add(Image::new(
+            Extent3d { width: 1, height: 4, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            texels.iter().flat_map(|value| value.to_le_bytes()).collect(),
+            TextureFormat::Rgba32Float,
+            RenderAssetUsages::default(),
+        ));
+        VATAnimation {
+            vertex_count: 1,
+            frame_count: 4,
+            frames_per_second: 30.0,
+            texture_width: 1,
+            rows_per_frame: 1,
+            bbox_min: Vec3::ZERO,
+            bbox_max: Vec3::splat(4.0),
+            positions,
+            normals: None,
+            mesh: None,
+            clips: vec![
+                VATClip { name: "a".into(), start_frame: 0, frame_count: 2 },
+                VATClip { name: "b".into(), start_frame: 2, frame_count: 2 },
+            ],
+            encoding: VATEncoding::default(),
+        }
+    }
+
+    fn sampled_x(animation: &VATAnimation, images: &Assets<Image>, clip: Option<&str>, frame: f32) -> f32 {
+        let clip = clip.map(|name| animation.clip(name).unwrap());
+        sample_vat_vertex(animation, images, clip, 0, Vec3::ZERO, frame).unwrap().position.x
+    }
+
+    #[test]
+    fn sampling_the_whole_texture_wraps_past_the_last_frame() {
+        let mut images = Assets::<Image>::default();
+        let animation = four_frame_animation(&mut images);
+        assert_eq!(sampled_x(&animation, &images, None, 1.25), 1.25);
+        assert_eq!(sampled_x(&animation, &images, None, 3.5), 1.5);
+    }
+
+    #[test]
+    fn sampling_a_clip_stays_within_its_frames() {
+        let mut images = Assets::<Image>::default();
+        let animation = four_frame_animation(&mut images);
+
+        // The last frame of a clip blends back to its own first frame, not
+        // into the next clip
+        assert_eq!(sampled_x(&animation, &images, Some("a"), 1.5), 0.5);
+        assert_eq!(sampled_x(&animation, &images, Some("b"), 0.25), 2.25);
+        assert_eq!(sampled_x(&animation, &images, Some("b"), 1.5), 2.5);
+        assert_eq!(sampled_x(&animation, &images, Some("b"), 2.0), 2.0);
+    }
+
+    #[test]
+    fn clip_ranges_fall_back_to_the_whole_texture() {
+        let mut images = Assets::<Image>::default();
+        let mut animations = Assets::<VATAnimation>::default();
+        let mut material = create_vat_material(Handle::default(), Handle::default(), None, 4, 1, 1, Vec3::ZERO, Vec3::ONE);
+        let mut animation = four_frame_animation(&mut images);
+        assert_eq!(material.clip_range(&animations, 1), (0, 4));
+
+        material.animation = Some(animations.add(four_frame_animation(&mut images)));
+        assert_eq!(material.clip_range(&animations, 1), (2, 2));
+        assert_eq!(material.clip_range(&animations, 2), (0, 4));
+
+        animation.clips.clear();
+        material.animation = Some(animations.add(animation));
+        assert_eq!(material.clip_range(&animations, 0), (0, 4));
+    }
+
+    #[test]
+    fn sampling_without_loaded_textures_is_none() {
+        let mut images = Assets::<Image>::default();
+        let animation = four_frame_animation(&mut images);
+        images.remove(&animation.positions);
+        assert!(sample_vat_vertex(&animation, &images, None, 0, Vec3::ZERO, 0.0).is_none());
+    }
+
+    /// A consistent header of two clips over 6 frames of 5 vertices
+    fn vat_file() -> VATFile {
+        VATFile {
+            vertex_count: 5,
+            frame_count: 6,
+            frames_per_second: 30.0,
+            texture_width: 2,
+            rows_per_frame: 3,
+            bbox_min: [-1.0, 0.0, -1.0],
+            bbox_max: [1.0, 2.0, 1.0],
+            positions: "rat_positions.png".into(),
+            normals: Some("rat_normals.png".into()),
+            mesh: None,
+            clips: vec![
+                VATClip { name: "idle".into(), start_frame: 0, frame_count: 4 },
+                VATClip { name: "walk".into(), start_frame: 4, frame_count: 2 },
+            ],
+            encoding: VATEncoding::default(),
+        }
+    }
+
+    #[test]
+    fn vat_files_round_trip_through_ron() {
+        let mut file = vat_file();
+        file.encoding.normals = VATNormalEncoding::Octahedral8;
+        let ron = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default()).unwrap();
+        let parsed: VATFile = ron::from_str(&ron).unwrap();
+        parsed.validate().unwrap();
+        assert_eq!(ron::ser::to_string_pretty(&parsed, ron::ser::PrettyConfig::default()).unwrap(), ron);
+        assert_eq!(parsed.texture_height(), 18);
+
+        // Files baked before encodings were added read as the default one
+        let legacy = ron.replace("encoding:", "_encoding:");
+        let legacy = &legacy[..legacy.find("_encoding:").unwrap()];
+        let parsed: VATFile = ron::from_str(&format!("{legacy})")).unwrap();
+        assert_eq!(parsed.encoding, VATEncoding::default());
+    }
+
+    fn rejection(file: VATFile) -> String {
+        match file.validate() {
+            Err(VATAnimationLoaderError::Invalid(reason)) => reason,
+            result => panic!("expected a rejection, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn inconsistent_headers_are_rejected() {
+        vat_file().validate().unwrap();
+
+        let zero_width = VATFile { texture_width: 0, ..vat_file() };
+        assert!(rejection(zero_width).contains("must be non-zero"));
+        let short_rows = VATFile { rows_per_frame: 2, ..vat_file() };
+        assert_eq!(rejection(short_rows), "rows_per_frame is 2, 5 vertices in rows of 2 need 3");
+        let inverted = VATFile { bbox_min: [0.0, 3.0, 0.0], ..vat_file() };
+        assert_eq!(rejection(inverted), "bbox_min is above bbox_max");
+
+        let mut past_the_end = vat_file();
+        past_the_end.clips[1].frame_count = 3;
+        assert_eq!(rejection(past_the_end), "clip walk covers frames 4..7 of 6");
+        let mut empty_clip = vat_file();
+        empty_clip.clips[0].frame_count = 0;
+        assert_eq!(rejection(empty_clip), "clip idle covers frames 0..0 of 6");
+
+        let rest_delta = VATFile { encoding: VATEncoding { rest_delta: true, ..default() }, ..vat_file() };
+        assert!(rejection(rest_delta).contains("need the mesh"));
+    }
+
+    #[test]
+    fn only_unorm16_normals_can_be_left_out() {
+        let without_normals = VATFile { normals: None, ..vat_file() };
+        without_normals.validate().unwrap();
+
+        let octahedral = VATEncoding { normals: VATNormalEncoding::Octahedral8, ..default() };
+        let missing = VATFile { normals: None, encoding: octahedral, ..vat_file() };
+        assert_eq!(rejection(missing), "Octahedral8 normals need a normals texture");
+        VATFile { encoding: octahedral, ..vat_file() }.validate().unwrap();
+    }
+
+    #[test]
+    fn the_loader_reads_the_fixture_header_and_textures() {
+        let mut app = crate::lod_system::bake::headless_app(|app| {
+            app.init_asset::<VATAnimation>().init_asset_loader::<VATAnimationLoader>();
+        });
+        // Baked from `fixtures/two_bone.gltf` with octahedral normals
+        let handle: Handle<VATAnimation> = app.world().resource::<AssetServer>().load("fixtures/two_bone.vat");
+        let started = std::time::Instant::now();
+        while !app.world().resource::<AssetServer>().is_loaded_with_dependencies(&handle) {
+            assert!(!app.world().resource::<AssetServer>().load_state(&handle).is_failed());
+            assert!(started.elapsed().as_secs() < 30, "the fixture didn't load");
+            app.update();
+        }
+
+        let animations = app.world().resource::<Assets<VATAnimation>>();
+        let images = app.world().resource::<Assets<Image>>();
+        let animation = animations.get(&handle).unwrap();
+        assert_eq!((animation.vertex_count, animation.frame_count, animation.texture_width), (4, 6, 4));
+        assert_eq!(animation.clips.iter().map(|clip| clip.name.as_str()).collect::<Vec<_>>(), ["Wave", "Slide"]);
+        assert_eq!(animation.mesh.as_deref(), Some("Mesh0/Primitive0"));
+
+        let positions = images.get(&animation.positions).unwrap();
+        assert_eq!(positions.size(), UVec2::new(4, 6));
+        let normals = images.get(animation.normals.as_ref().unwrap()).unwrap();
+        assert_eq!(normals.texture_descriptor.format, TextureFormat::Rg8Unorm);
+
+        // Halfway through `Wave` the top corner has turned an eighth about
+        // joint 1, a unit above the origin
+        let wave = animation.clip("Wave");
+        let sample = sample_vat_vertex(animation, images, wave, 3, Vec3::new(1.0, 2.0, 0.0), 2.0).unwrap();
+        assert!(sample.position.abs_diff_eq(Vec3::new(0.0, 1.0 + std::f32::consts::SQRT_2, 0.0), 1e-3));
+        assert!(sample.normal.abs_diff_eq(Vec3::Z, 1e-2));
+    }
+}
@@ -0,0 +1,479 @@
+use bevy::prelude::*;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::platform::collections::HashMap;
+use bevy::render::mesh::{MeshTag, MeshVertexBufferLayoutRef};
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, BufferUsages, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+    encase::StorageBuffer,
+};
+use bevy::render::renderer::RenderQueue;
+use bevy::render::storage::{GpuShaderStorageBuffer, ShaderStorageBuffer};
+use bevy::render::{Render, RenderApp, RenderSet};
+
+use crate::lod_system::fade::{LODFade, LODFadeOwnTag, dithered_alpha_mode};
+use crate::lod_system::strategies::{HybridLODData, VATLODData};
+use crate::lod_system::vat::{
+    VATAnimatedMaterial, VATAnimation, VATAnimationState, VATEncoding, advance_vat_animations,
+    apply_vat_animations, specialize_vat_encoding, sync_vat_lod_playback,
+};
+
+// GPU-instanced VAT rendering: one material per VAT, per-entity playback in a
+// storage buffer
+
+/// VAT material shared by every entity playing the same `VATAnimation`.
+///
+/// Unlike `VATMaterial` the frame and clips aren't material parameters: each
+/// entity's `VATAnimationState` is written to `instances` every frame, and the
+/// entity's `MeshTag` is its slot there. Entities sharing the material and mesh
+/// are drawn in one instanced draw call while playing independently.
+#[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
+#[bind_group_data(VATEncoding)]
+#[uniform(0, VATInstancedMaterialUniform)]
+pub struct VATInstancedMaterial {
+    pub total_frames: f32,
+    pub vertex_count: u32,
+    pub texture_width: u32,
+    pub bbox_min: Vec3,
+    pub bbox_max: Vec3,
+
+    #[texture(1)]
+    #[sampler(3)]
+    pub position_texture: Handle<Image>,
+
+    #[texture(2)]
+    #[sampler(4)]
+    pub normal_texture: Handle<Image>,
+
+    #[texture(5)]
+    #[sampler(6)]
+    pub base_color_texture: Option<Handle<Image>>,
+
+    // `VATInstanceData` of every entity using the material
+    #[storage(7, read_only)]
+    pub instances: Handle<ShaderStorageBuffer>,
+
+    pub alpha_mode: AlphaMode,
+
+    pub encoding: VATEncoding,
+
+    pub animation: Option<Handle<VATAnimation>>,
+}
+
+impl VATInstancedMaterial {
+    /// Material for a `.vat` file, filled in once the `VATAnimation` has loaded
+    pub fn from_animation(
+        animation: Handle<VATAnimation>,
+        base_color_texture: Option<Handle<Image>>,
+        buffers: &mut Assets<ShaderStorageBuffer>,
+    ) -> Self {
+        Self {
+            total_frames: 1.0,
+            vertex_count: 0,
+            texture_width: 1,
+            bbox_min: Vec3::ZERO,
+            bbox_max: Vec3::ZERO,
+            position_texture: Handle::default(),
+            normal_texture: Handle::default(),
+            base_color_texture,
+            instances: buffers.add(instance_buffer(vec![VATInstanceData::default()])),
+            alpha_mode: AlphaMode::Opaque,
+            encoding: VATEncoding::default(),
+            animation: Some(animation),
+        }
+    }
+}
+
+impl From<&VATInstancedMaterial> for VATInstancedMaterialUniform {
+    fn from(material: &VATInstancedMaterial) -> Self {
+        Self {
+            total_frames: material.total_frames,
+            vertex_count: material.vertex_count,
+            texture_width: material.texture_width,
+            bbox_min: material.bbox_min,
+            bbox_max: material.bbox_max,
+        }
+    }
+}
+
+impl From<&VATInstancedMaterial> for VATEncoding {
+    fn from(material: &VATInstancedMaterial) -> Self {
+        material.encoding
+    }
+}
+
+impl VATAnimatedMaterial for VATInstancedMaterial {
+    fn animation(&self) -> Option<&Handle<VATAnimation>> {
+        self.animation.as_ref()
+    }
+
+    fn total_frames(&self) -> u32 {
+        self.total_frames as u32
+    }
+
+    fn apply_animation(&mut self, animation: &VATAnimation) {
+        self.total_frames = animation.frame_count as f32;
+        self.vertex_count = animation.vertex_count;
+        self.texture_width = animation.texture_width;
+        self.bbox_min = animation.bbox_min;
+        self.bbox_max = animation.bbox_max;
+        self.position_texture = animation.positions.clone();
+        self.normal_texture = animation.normals.clone().unwrap_or_default();
+        self.encoding = animation.encoding;
+    }
+}
+
+impl Material for VATInstancedMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/vat_shader.wgsl".into()
+    }
+
+    fn vertex_shader() -> ShaderRef {
+        "shaders/vat_shader.wgsl".into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        "shaders/vat_shader.wgsl".into()
+    }
+
+    // Dithers LOD fades in the prepass and shadow pass
+    fn prepass_fragment_shader() -> ShaderRef {
+        "shaders/vat_shader.wgsl".into()
+    }
+
+    // Any instance may be fading, so the material is always masked
+    fn alpha_mode(&self) -> AlphaMode {
+        dithered_alpha_mode(self.alpha_mode)
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.vertex.shader_defs.push("VAT_INSTANCED".into());
+        if let Some(fragment) = &mut descriptor.fragment {
+            fragment.shader_defs.push("VAT_INSTANCED".into());
+        }
+        specialize_vat_encoding(descriptor, &key.bind_group_data);
+        Ok(())
+    }
+}
+
+pub use shader_types::{VATInstanceData, VATInstancedMaterialUniform};
+
+// The field checks `ShaderType` derives are never called and trip `dead_code`
+#[allow(dead_code)]
+mod shader_types {
+    use bevy::prelude::Vec3;
+    use bevy::render::render_resource::ShaderType;
+
+    /// `VATInstancedMaterial` at binding 0, `VATUniforms` in `vat_shader.wgsl`
+    #[derive(ShaderType, Clone, Default)]
+    pub struct VATInstancedMaterialUniform {
+        pub total_frames: f32,
+        pub vertex_count: u32,
+        pub texture_width: u32,
+        pub bbox_min: Vec3,
+        pub bbox_max: Vec3,
+    }
+
+    /// One entity's playback, as `vat_shader.wgsl` reads it with `VAT_INSTANCED`
+    #[derive(ShaderType, Clone, Copy, Debug, Default, PartialEq)]
+    pub struct VATInstanceData {
+        pub current_frame: f32,
+        pub clip_start: u32,
+        pub clip_frame_count: u32,
+        pub elapsed_frames: f32, // Frames played since the last frame, for motion vectors
+        pub previous_frame: f32,
+        pub previous_clip_start: u32,
+        pub previous_clip_frame_count: u32,
+        pub clip_blend: f32,
+        pub fade: u32,          // `LODFade::mesh_tag`, 0 when not fading
+    }
+}
+
+/// Adds `VATInstancedMaterial` and plays the `VATRepresentation`s of the VAT
+/// LOD strategies with it. Needs the `VATMaterialPlugin` for `.vat` files.
+pub struct VATInstancingPlugin;
+
+impl Plugin for VATInstancingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+                MaterialPlugin::<VATInstancedMaterial>::default(),
+                ExtractResourcePlugin::<VATInstanceUploads>::default(),
+            ))
+            .init_resource::<VATInstanceUploads>()
+            .add_systems(Update, (
+                apply_vat_animations::<VATInstancedMaterial>,
+                advance_vat_animations::<VATInstancedMaterial>,
+                sync_vat_lod_playback::<VATLODData, VATInstancedMaterial>,
+                sync_vat_lod_playback::<HybridLODData, VATInstancedMaterial>,
+            ).chain())
+            .add_systems(PostUpdate, (assign_vat_instance_slots, write_vat_instances).chain());
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(Render, upload_vat_instances.in_set(RenderSet::PrepareResources));
+        }
+    }
+}
+
+/// The playback `write_vat_instances` packed this frame, by instance buffer.
+/// Copied into the buffers already on the GPU, so the materials binding them
+/// aren't prepared again.
+#[derive(Resource, Clone, Default, ExtractResource)]
+struct VATInstanceUploads(Vec<(AssetId<ShaderStorageBuffer>, Vec<u8>)>);
+
+/// Instance buffer holding `instances`, which `upload_vat_instances` can
+/// write to
+fn instance_buffer(instances: Vec<VATInstanceData>) -> ShaderStorageBuffer {
+    let mut buffer = ShaderStorageBuffer::from(instances);
+    buffer.buffer_description.usage |= BufferUsages::COPY_DST;
+    buffer
+}
+
+fn encode_instances(instances: &Vec<VATInstanceData>) -> Vec<u8> {
+    let mut bytes = StorageBuffer::new(Vec::new());
+    bytes.write(instances).unwrap();
+    bytes.into_inner()
+}
+
+/// Gives every entity using a `VATInstancedMaterial` a slot in the material's
+/// instance buffer, as its `MeshTag`
+fn assign_vat_instance_slots(
+    mut commands: Commands,
+    query: Query<(Entity, &MeshMaterial3d<VATInstancedMaterial>, Option<&MeshTag>)>,
+) {
+    let mut slot_counts: HashMap<AssetId<VATInstancedMaterial>, u32> = HashMap::default();
+    for (entity, material, tag) in query.iter() {
+        let slot_count = slot_counts.entry(material.0.id()).or_default();
+        let slot = *slot_count;
+        *slot_count += 1;
+
+        if tag.is_none_or(|tag| tag.0 != slot) {
+            commands.entity(entity).insert((MeshTag(slot), LODFadeOwnTag));
+        }
+    }
+}
+
+/// Packs the playback of every entity using a `VATInstancedMaterial` into its
+/// slot of the material's instance buffer, as a `VATInstanceUploads`
+#[allow(clippy::too_many_arguments)]
+fn write_vat_instances(
+    time: Res<Time>,
+    animations: Res<Assets<VATAnimation>>,
+    mut materials: ResMut<Assets<VATInstancedMaterial>>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    mut uploads: ResMut<VATInstanceUploads>,
+    query: Query<(Entity, &MeshMaterial3d<VATInstancedMaterial>, &VATAnimationState, &MeshTag)>,
+    fades: Query<&LODFade>,
+    parents: Query<&ChildOf>,
+) {
+    let mut instances: HashMap<AssetId<VATInstancedMaterial>, Vec<VATInstanceData>> = HashMap::default();
+    for (entity, material_handle, animation_state, tag) in query.iter() {
+        let Some(material) = materials.get(&material_handle.0) else {
+            continue;
+        };
+
+        // Fades of a VAT entity or of a representation it is part of
+        let fade = if fades.is_empty() {
+            None
+        } else {
+            std::iter::once(entity)
+                .chain(parents.iter_ancestors(entity))
+                .find_map(|entity| fades.get(entity).ok())
+        };
+        let playback = animation_state.playback(material, &animations);
+        let elapsed_frames = if animation_state.is_playing {
+            time.delta_secs() * animation_state.playback_speed * material.frames_per_second(&animations)
+        } else {
+            0.0
+        };
+
+        let material_instances = instances.entry(material_handle.0.id()).or_default();
+        let slot = tag.0 as usize;
+        if material_instances.len() <= slot {
+            material_instances.resize(slot + 1, VATInstanceData::default());
+        }
+        material_instances[slot] = VATInstanceData {
+            current_frame: playback.current_frame,
+            clip_start: playback.clip_start,
+            clip_frame_count: playback.clip_frame_count,
+            elapsed_frames,
+            previous_frame: playback.previous_frame,
+            previous_clip_start: playback.previous_clip_start,
+            previous_clip_frame_count: playback.previous_clip_frame_count,
+            clip_blend: playback.clip_blend,
+            fade: fade.map_or(0, LODFade::mesh_tag),
+        };
+    }
+
+    uploads.0.clear();
+    for (id, mut material_instances) in instances {
+        let Some(buffer_handle) = materials.get(id).map(|material| material.instances.clone()) else {
+            continue;
+        };
+        let Some(buffer) = buffers.get(&buffer_handle) else {
+            continue;
+        };
+        let bytes = encode_instances(&material_instances);
+
+        // Outgrown buffers are replaced, which only the material being
+        // prepared again binds. Doubled so that happens rarely.
+        if bytes.len() > buffer.data.as_ref().map_or(0, Vec::len) {
+            material_instances.resize(material_instances.len().next_power_of_two(), VATInstanceData::default());
+            if let Some(buffer) = buffers.get_mut(&buffer_handle) {
+                buffer.set_data(material_instances);
+            }
+            materials.get_mut(id);
+        }
+        uploads.0.push((buffer_handle.id(), bytes));
+    }
+}
+
+/// Writes the playback of `VATInstanceUploads` into the instance buffers
+fn upload_vat_instances(
+    uploads: Res<VATInstanceUploads>,
+    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    queue: Res<RenderQueue>,
+) {
+    for (id, bytes) in &uploads.0 {
+        // Not prepared yet, or still the outgrown buffer
+        let Some(buffer) = buffers.get(*id).filter(|buffer| bytes.len() as u64 <= buffer.buffer.size()) else {
+            continue;
+        };
+        queue.write_buffer(&buffer.buffer, 0, bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn instanced_material(world: &mut World) -> Handle<VATInstancedMaterial> {
+        let mut material = {
+            let mut buffers = world.get_resource_or_init::<Assets<ShaderStorageBuffer>>();
+            VATInstancedMaterial::from_animation(Handle::default(), None, &mut buffers)
+        };
+        material.animation = None;
+        material.total_frames = 40.0;
+        world.get_resource_or_init::<Assets<VATInstancedMaterial>>().add(material)
+    }
+
+    fn instancing_world() -> World {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(std::time::Duration::from_millis(50));
+        world.insert_resource(time);
+        world.init_resource::<Assets<VATAnimation>>();
+        world.init_resource::<VATInstanceUploads>();
+        world
+    }
+
+    fn uploads(world: &World) -> Vec<Vec<u8>> {
+        world.resource::<VATInstanceUploads>().0.iter().map(|(_, bytes)| bytes.clone()).collect()
+    }
+
+    fn instances(world: &World, material: &Handle<VATInstancedMaterial>) -> Option<Vec<u8>> {
+        let material = world.resource::<Assets<VATInstancedMaterial>>().get(material).unwrap();
+        world.resource::<Assets<ShaderStorageBuffer>>().get(&material.instances).unwrap().data.clone()
+    }
+
+    fn playing(current_frame: f32, fade: u32) -> VATInstanceData {
+        VATInstanceData {
+            current_frame,
+            clip_frame_count: 40,
+            elapsed_frames: 1.5,
+            previous_clip_frame_count: 40,
+            clip_blend: 1.0,
+            fade,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn every_material_numbers_its_entities_from_zero() {
+        let mut world = World::new();
+        let rats = instanced_material(&mut world);
+        let cheeses = instanced_material(&mut world);
+        let entities = [&rats, &cheeses, &rats, &cheeses, &rats]
+            .map(|material| world.spawn(MeshMaterial3d(material.clone())).id());
+
+        world.run_system_once(assign_vat_instance_slots).unwrap();
+
+        let slots = |material: &Handle<VATInstancedMaterial>| {
+            let mut slots: Vec<u32> = entities.iter()
+                .filter(|&&entity| world.get::<MeshMaterial3d<VATInstancedMaterial>>(entity).unwrap().0 == *material)
+                .map(|&entity| world.get::<MeshTag>(entity).unwrap().0)
+                .collect();
+            slots.sort();
+            slots
+        };
+        assert_eq!(slots(&rats), [0, 1, 2]);
+        assert_eq!(slots(&cheeses), [0, 1]);
+        assert!(entities.iter().all(|&entity| world.get::<LODFadeOwnTag>(entity).is_some()));
+    }
+
+    #[test]
+    fn every_entity_is_written_to_its_slot() {
+        let mut world = instancing_world();
+        let material = instanced_material(&mut world);
+        // Fading out as part of a representation, at a quarter of the fade
+        let mut fade = LODFade::fade_out(1.0);
+        fade.elapsed = 0.25;
+        let representation = world.spawn(fade).id();
+        world.spawn((
+            MeshMaterial3d(material.clone()),
+            VATAnimationState::default().with_frame(12.0),
+            MeshTag(1),
+            ChildOf(representation),
+        ));
+        world.spawn((
+            MeshMaterial3d(material.clone()),
+            VATAnimationState::default().with_frame(3.0),
+            MeshTag(0),
+        ));
+
+        world.run_system_once(write_vat_instances).unwrap();
+
+        let expected = ShaderStorageBuffer::from(vec![playing(3.0, 0), playing(12.0, fade.mesh_tag())]);
+        assert_eq!(uploads(&world), [expected.data.clone().unwrap()]);
+        // Outgrowing the single slot buffer replaced it
+        assert_eq!(instances(&world, &material), expected.data);
+    }
+
+    #[test]
+    fn instance_buffers_are_only_replaced_when_outgrown() {
+        let mut world = instancing_world();
+        let material = instanced_material(&mut world);
+        let spawn = |world: &mut World, slot| {
+            world.spawn((MeshMaterial3d(material.clone()), VATAnimationState::default(), MeshTag(slot))).id()
+        };
+        let first = spawn(&mut world, 0);
+        let seek = |world: &mut World, frame| {
+            world.entity_mut(first).get_mut::<VATAnimationState>().unwrap().current_frame = frame;
+        };
+
+        // Fits the buffer `from_animation` made, so it's only uploaded
+        seek(&mut world, 5.0);
+        world.run_system_once(write_vat_instances).unwrap();
+        assert_eq!(uploads(&world), [ShaderStorageBuffer::from(vec![playing(5.0, 0)]).data.unwrap()]);
+        assert_eq!(instances(&world, &material), ShaderStorageBuffer::from(vec![VATInstanceData::default()]).data);
+
+        // Three slots double to four
+        spawn(&mut world, 1);
+        spawn(&mut world, 2);
+        world.run_system_once(write_vat_instances).unwrap();
+        let grown = vec![playing(5.0, 0), playing(0.0, 0), playing(0.0, 0), VATInstanceData::default()];
+        assert_eq!(instances(&world, &material), ShaderStorageBuffer::from(grown.clone()).data);
+
+        seek(&mut world, 9.0);
+        world.run_system_once(write_vat_instances).unwrap();
+        assert_eq!(instances(&world, &material), ShaderStorageBuffer::from(grown).data);
+        let written = ShaderStorageBuffer::from(vec![playing(9.0, 0), playing(0.0, 0), playing(0.0, 0)]);
+        assert_eq!(uploads(&world), [written.data.unwrap()]);
+    }
+}
@@ -1,7 +1,7 @@
 mod lod;
-mod lod_system;
 
 use bevy::prelude::*;
+use bevy::render::storage::ShaderStorageBuffer;
 use bevy_asset_loader::prelude::*;
 use bevy_inspector_egui::egui::debug_text::print;
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
@@ -9,8 +9,17 @@ use std::time::Duration;
 use iyes_perf_ui::prelude::*;
 
 // Import the new LOD system
+use hairyrat::lod_system;
 use lod_system::prelude::*;
-use lod_system::strategies::{MeshSwapLODConfig, HybridLODStrategy, HybridLODConfig, HybridLODData, AnimationLODConfig};
+use lod_system::animation::clip_duration;
+use lod_system::bake::{bake_vat, VATBakeSettings};
+use lod_system::validation::{validate_lod_dither_shaders, validate_vat_shader};
+use lod_system::strategies::{MeshSwapLODConfig, HybridLODStrategy, HybridLODConfig, HybridLODData, AnimationLODConfig, DisabledAnimationMode, VATLODConfig};
+
+/// Baked with `hairyrat bake-vat blackrat_furless/rat_without_fur.glb 0,1 assets/vat rat low 30 15`,
+/// low LOD rats play it when it exists. Holds every clip of `RatAssets::animation_clips`
+/// in order, so a rat's `clip_index` picks the same clip on both paths.
+const RAT_VAT_PATH: &str = "vat/rat_low.vat";
 
 #[derive(Resource)]
 struct Animations {
@@ -18,18 +27,104 @@ struct Animations {
     node_indices: Vec<AnimationNodeIndex>,
 }
 
+impl Animations {
+    /// Graph node playing clip `clip_index`, wrapping past the last clip
+    fn node(&self, clip_index: usize) -> AnimationNodeIndex {
+        self.node_indices[clip_index % self.node_indices.len()]
+    }
+}
+
 // Rat entity marker
 #[derive(Component)]
 pub struct Rat;
 
+/// Per-rat animation variation so the crowd doesn't animate in lockstep
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RatAnimationVariation {
+    pub phase_offset: f32,     // Fraction of the clip to start at
+    pub speed_multiplier: f32,
+    pub clip_index: usize,     // Index into `Animations::node_indices`
+}
+
+impl RatAnimationVariation {
+    /// Deterministic variation for `seed`, picking one of `clip_count` clips
+    pub fn from_seed(seed: u64, clip_count: usize) -> Self {
+        // splitmix64
+        let mut hash = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^= hash >> 31;
+        
+        let unit = |bits: u64| (bits & 0xFFFF) as f32 / 65535.0;
+        Self {
+            phase_offset: unit(hash),
+            speed_multiplier: 0.85 + 0.3 * unit(hash >> 16),
+            clip_index: (hash >> 32) as usize % clip_count.max(1),
+        }
+    }
+}
+
 fn main() {
+    // `hairyrat bake-vat ...` bakes VAT textures headlessly instead of running the demo
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("bake-vat") {
+        match VATBakeSettings::from_args(&args[2..]).and_then(bake_vat) {
+            Ok(baked) => {
+                println!("Baked {} frames of {} vertices", baked.vat.frame_count, baked.vat.vertex_count);
+                println!("Encoding {}", baked.report);
+                for alternative in &baked.alternatives {
+                    println!("    vs {alternative}");
+                }
+            }
+            Err(error) => {
+                eprintln!("VAT bake failed: {error}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    // `hairyrat validate-shaders` checks the VAT and dither shaders compile,
+    // without a GPU
+    if args.get(1).map(String::as_str) == Some("validate-shaders") {
+        let results = [
+            ("vat_shader.wgsl", validate_vat_shader()),
+            ("LOD dither shaders", validate_lod_dither_shaders()),
+        ];
+        for (shaders, result) in results {
+            match result {
+                Ok(variants) => println!("{shaders}: valid ({})", variants.join(", ")),
+                Err(error) => {
+                    eprintln!("Shader validation failed: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
+
     App::new()
         .add_plugins((
             DefaultPlugins,
             PanOrbitCameraPlugin,
         ))
+        // Cap LOD churn at 2ms of transitions per frame, scene swaps are expensive
+        .insert_resource(LODProcessingBudget::new(50).with_time_budget_micros(2000))
         // Use the hybrid LOD system that combines multiple strategies
-        .add_plugins(LODPlugin::<Rat, HybridLODStrategy>::default())
+        .add_plugins(
+            LODPlugin::<Rat, HybridLODStrategy>::default()
+                .run_if(in_state(AppState::InGame)),
+        )
+        // Advance rat animations at their LOD's update rate, staggered across frames
+        .add_plugins(AnimationSchedulerPlugin::<Rat, HybridLODStrategy>::default())
+        // Keep animation time when the mesh swap replaces a rat's scene
+        .add_plugins(AnimationHandoffPlugin::<Rat>::default())
+        // Reuse pre-spawned rat hierarchies instead of respawning scenes on every swap
+        .add_plugins(ScenePoolPlugin { max_idle_per_scene: 512 })
+        // Dither between the outgoing and incoming rat scenes instead of popping
+        .add_plugins(LODFadePlugin)
+        // Low LOD rats play a baked VAT instead of a skinned scene, all of
+        // them in one instanced draw
+        .add_plugins((VATMaterialPlugin, VATInstancingPlugin))
         .insert_resource(LODLevels::<Rat>::new(create_standard_lod_levels()))
         .init_state::<AppState>()
         .add_loading_state(
@@ -40,15 +135,21 @@ fn main() {
         )
         .add_systems(OnEnter(AppState::Loading), show_loading_screen)
         .add_systems(OnExit(AppState::Loading), hide_loading_screen)
-        .add_systems(OnEnter(AppState::InGame), setup_scene)
+        .add_systems(OnEnter(AppState::InGame), (setup_scene, setup_rat_vat).chain())
         .add_systems(
             Update,
             (
                 setup_initial_animations,
-                handle_animation_lod,
+                apply_vat_variation,
                 debug_lod_stats,
             ).run_if(in_state(AppState::InGame))
         )
+        .add_systems(
+            PostUpdate,
+            handle_animation_lod
+                .after(LODSystemSet::Transition)
+                .run_if(in_state(AppState::InGame)),
+        )
         .run();
 }
 
@@ -56,9 +157,14 @@ fn setup_scene(
     mut commands: Commands,
     rat_assets: Res<RatAssets>,
     mut graphs: ResMut<Assets<AnimationGraph>>,
+    mut scene_pool: ResMut<ScenePool>,
     env: Res<EnvironmentAssets>,
 ) {
     commands.spawn(PerfUiAllEntries::default());
+    
+    // Most swaps are between the two rat scenes at mid distance
+    scene_pool.prewarm(rat_assets.rat.clone(), 128);
+    scene_pool.prewarm(rat_assets.rat_lod0.clone(), 256);
 
     // Setup Hybrid LOD configuration that combines multiple strategies
     let hybrid_config = HybridLODConfig {
@@ -66,8 +172,11 @@ fn setup_scene(
             high_quality_distance: 10.0,
             medium_quality_distance: 25.0,
             low_quality_distance: 50.0,
+            // Distant rats hold their pose instead of snapping back to bind pose
+            disabled_mode: DisabledAnimationMode::Freeze,
+            ..default()
         },
-        vat_config: Default::default(), // Not using VAT for now
+        vat_config: Default::default(), // Filled in by `setup_rat_vat`
         mesh_swap_config: MeshSwapLODConfig {
             mesh_handles: vec![],
             material_handles: vec![],
@@ -80,8 +189,11 @@ fn setup_scene(
                 rat_assets.rat_lod0.clone(),
                 // Level 3: Hidden
             ],
+            pool_scenes: true,
+            fade_duration: 0.3,
+            ..default()
         },
-        use_vat_at_level: 99, // Never switch to VAT (you can change this)
+        use_vat_at_level: 99, // Never switch to VAT, unless `setup_rat_vat` finds one
     };
     commands.insert_resource(hybrid_config);
 
@@ -104,17 +216,19 @@ fn setup_scene(
         Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, -0.5, -0.5, 0.0)),
     ));
 
-    // Setup animation - use animation from the main animated GLB file
-    let (graph, index) = AnimationGraph::from_clip(rat_assets.animation_clip.clone());
+    // Setup animation - every clip of the main animated GLB file, each rat
+    // picks one through its `RatAnimationVariation`
+    let (graph, node_indices) = AnimationGraph::from_clips(rat_assets.animation_clips.iter().cloned());
     let graph_handle = graphs.add(graph);
     let spawn_base = commands
         .spawn((Transform::default(), Visibility::default(), Rat))
         .id();
 
 
+    let clip_count = node_indices.len();
     commands.insert_resource(Animations {
         graph: graph_handle.clone(),
-        node_indices: vec![index],
+        node_indices,
     });
 
     // Spawn rats with strategy-based LOD system
@@ -136,6 +250,7 @@ fn setup_scene(
                 AnimationGraphHandle(graph_handle.clone()),
                 Rat,
                 ChildOf(spawn_base),
+                RatAnimationVariation::from_seed((x * 100 + y) as u64, clip_count),
                 // Components for hybrid LOD system
                 LODState::new(initial_lod),
                 LODDistance::default(),
@@ -145,38 +260,140 @@ fn setup_scene(
     ));
 }
 
+// Rats at level 2 swap their scene for the baked VAT, if there is one
+fn setup_rat_vat(
+    mut hybrid_config: ResMut<HybridLODConfig>,
+    rat_assets: Res<RatAssets>,
+    asset_server: Res<AssetServer>,
+    mut vat_materials: ResMut<Assets<VATInstancedMaterial>>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    clips: Res<Assets<AnimationClip>>,
+) {
+    let rat_vat_baked = bevy::asset::io::file::FileAssetReader::get_base_path()
+        .join("assets")
+        .join(RAT_VAT_PATH)
+        .exists();
+    if !rat_vat_baked {
+        return;
+    }
+
+    // Shared by every VAT rat, each plays its own frame in an instance slot
+    let material = vat_materials.add(VATInstancedMaterial::from_animation(
+        asset_server.load(RAT_VAT_PATH),
+        Some(asset_server.load("blackrat_free_glb/blackrat_color.png")),
+        &mut buffers,
+    ));
+    // `VATLODData` advances 30 frames a second, loop each clip with its skeletal clip
+    let clip_frame_counts = rat_assets.animation_clips.iter()
+        .map(|clip| clips.get(clip).map_or(60, |clip| (clip.duration() * 30.0).round().max(1.0) as u32))
+        .collect();
+    hybrid_config.vat_config = VATLODConfig {
+        // The skinned primitive the VAT was baked from
+        meshes: vec![asset_server.load("blackrat_furless/rat_without_fur.glb#Mesh0/Primitive0")],
+        // Indexed by level like the other configs, only level 2 uses VAT
+        materials: vec![material; 3],
+        clip_frame_counts,
+        playback_speeds: vec![1.0; 3],
+        // Cross-fades with the scenes like their own swaps do
+        fade_duration: hybrid_config.mesh_swap_config.fade_duration,
+        ..default()
+    };
+    hybrid_config.use_vat_at_level = 2;
+}
+
+// Starts the rat's clip at its phase offset and speed
+fn play_rat_animation(
+    player: &mut AnimationPlayer,
+    animations: &Animations,
+    variation: Option<&RatAnimationVariation>,
+    graphs: &Assets<AnimationGraph>,
+    clips: &Assets<AnimationClip>,
+) -> AnimationTransitions {
+    let variation = variation.copied().unwrap_or(RatAnimationVariation {
+        phase_offset: 0.0,
+        speed_multiplier: 1.0,
+        clip_index: 0,
+    });
+    let node = animations.node(variation.clip_index);
+    let duration = graphs.get(&animations.graph)
+        .and_then(|graph| clip_duration(graph, clips, node))
+        .unwrap_or(0.0);
+    
+    let mut transitions = AnimationTransitions::new();
+    transitions
+        .play(player, node, Duration::from_millis(15))
+        .repeat()
+        .set_speed(variation.speed_multiplier)
+        .set_seek_time(variation.phase_offset * duration);
+    transitions
+}
+
+// Finds the variation of the rat a scene descendant belongs to
+fn find_rat_variation<'a>(
+    entity: Entity,
+    parents: &Query<&ChildOf>,
+    variations: &'a Query<&RatAnimationVariation>,
+) -> Option<&'a RatAnimationVariation> {
+    parents.iter_ancestors(entity).find_map(|ancestor| variations.get(ancestor).ok())
+}
+
 // System to setup initial animations for all rats
 fn setup_initial_animations(
     animations: Res<Animations>,
+    graphs: Res<Assets<AnimationGraph>>,
+    clips: Res<Assets<AnimationClip>>,
     mut commands: Commands,
     query: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+    parents: Query<&ChildOf>,
+    variations: Query<&RatAnimationVariation>,
 ) { 
-    for (_entity, mut player) in query {
-
-         let mut animation_transitions = AnimationTransitions::new();
-        animation_transitions
-            .play(
-                &mut player,
-                animations.node_indices[0],
-                Duration::from_millis(15),
-            )
-            .repeat();
+    for (entity, mut player) in query {
+        // Players of swapped scenes already carry the previous scene's animation
+        if player.playing_animations().next().is_some() {
+            continue;
+        }
+        
+        let variation = find_rat_variation(entity, &parents, &variations);
+        let animation_transitions =
+            play_rat_animation(&mut player, &animations, variation, &graphs, &clips);
 
         commands
-            .entity(_entity)
+            .entity(entity)
            .insert(AnimationGraphHandle(animations.graph.clone()))
            .insert(animation_transitions);
     }
 }
 
+// Carries each rat's variation over to its VAT playback, counting the frames of its clip
+fn apply_vat_variation(
+    hybrid_config: Res<HybridLODConfig>,
+    mut query: Query<(&RatAnimationVariation, &mut HybridLODData), Added<RatAnimationVariation>>,
+) {
+    let vat_level = hybrid_config.use_vat_at_level as usize;
+    for (variation, mut hybrid_lod_data) in query.iter_mut() {
+        let vat_data = &mut hybrid_lod_data.vat_data;
+        vat_data.speed_multiplier = variation.speed_multiplier;
+        vat_data.clip_index = variation.clip_index;
+        vat_data.frame_count = hybrid_config.vat_config.frame_count(vat_level, variation.clip_index);
+        vat_data.current_frame = variation.phase_offset * vat_data.frame_count as f32;
+    }
+}
+
 // System to handle animation based on hybrid LOD data
 fn handle_animation_lod(
     animations: Res<Animations>,
+    graphs: Res<Assets<AnimationGraph>>,
+    clips: Res<Assets<AnimationClip>>,
     mut commands: Commands,
-    mut query: Query<(Entity, &HybridLODData, &Children), (With<Rat>, Changed<HybridLODData>)>,
+    mut lod_transitions: EventReader<LODTransitioned>,
+    query: Query<(&HybridLODData, &Children, Option<&RatAnimationVariation>), With<Rat>>,
     mut animation_players: Query<&mut AnimationPlayer>,
 ) {
-    for (_entity, hybrid_lod_data, children) in query.iter_mut() {
+    for transition in lod_transitions.read() {
+        let Ok((hybrid_lod_data, children, variation)) = query.get(transition.entity) else {
+            continue;
+        };
+        
         // Access the animation data from the hybrid strategy
         let lod_data = &hybrid_lod_data.animation_data;
         
@@ -185,19 +402,15 @@ fn handle_animation_lod(
             if let Ok(mut player) = animation_players.get_mut(child) {
                 if lod_data.animation_enabled {
                     // Ensure animation is playing
-                    if !player.is_playing_animation(animations.node_indices[0]) {
-                        let mut transitions = AnimationTransitions::new();
-                        transitions
-                            .play(&mut player, animations.node_indices[0], Duration::from_millis(15))
-                            .repeat();
+                    if player.playing_animations().next().is_none() {
+                        let transitions =
+                            play_rat_animation(&mut player, &animations, variation, &graphs, &clips);
                         commands.entity(child).insert(transitions);
                     }
                     
-                    // Adjust playback speed based on LOD
-                    // Note: In Bevy 0.16, AnimationPlayer doesn't have set_speed method
-                    // Speed control would need to be implemented differently
-                } else {
-                    // Stop animation for low LOD
+                    // How often the player is updated follows `update_rate`, see AnimationScheduler
+                } else if !lod_data.frozen {
+                    // Stop animation for low LOD, frozen players are held by the AnimationScheduler
                     player.stop_all();
                 }
             }
@@ -207,6 +420,8 @@ fn handle_animation_lod(
 
 fn debug_lod_stats(
     query: Query<(&LODState, &LODDistance, &HybridLODData, Option<&AnimationPlayer>), With<Rat>>,
+    budget: Res<LODProcessingBudget>,
+    scene_pool: Res<ScenePool>,
     time: Res<Time>,
     mut last_print: Local<f32>,
 ) {
@@ -256,6 +471,12 @@ fn debug_lod_stats(
         
         println!("Animated: {} / {} ({:.1}% performance saving)", 
             animated_count, total, (1.0 - animated_count as f32 / total as f32) * 100.0);
+        println!("Scene pool: {} idle, {} hits, {} misses, {} evictions",
+            scene_pool.total_idle(), scene_pool.stats.hits, scene_pool.stats.misses,
+            scene_pool.stats.evictions);
+        println!("Transitions last frame: {} ({}µs), deferred: {} (longest wait {:.2}s)",
+            budget.last_frame.operations, budget.last_frame.time.as_micros(),
+            budget.last_frame.deferred_operations, budget.last_frame.longest_deferred_wait);
         
         *last_print = time.elapsed_secs();
     }
@@ -269,8 +490,8 @@ struct RatAssets {
     rat_lod0: Handle<Scene>,
     #[asset(path = "blackrat_furless/rat_without_fur.glb#Animation1")]
     rat_lod0_animation: Handle<AnimationClip>,
-    #[asset(path = "blackrat_free_glb/blackrat.glb#Animation1")]
-    animation_clip: Handle<AnimationClip>,
+    #[asset(paths("blackrat_free_glb/blackrat.glb#Animation0", "blackrat_free_glb/blackrat.glb#Animation1"), collection(typed))]
+    animation_clips: Vec<Handle<AnimationClip>>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -318,3 +539,49 @@ fn hide_loading_screen(
 
 #[derive(Component)]
 struct LoadingScreenMarker;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variations_pick_every_clip_deterministically() {
+        let clip_count = 2;
+        let mut picks = [0; 2];
+        for seed in 0..1000 {
+            let variation = RatAnimationVariation::from_seed(seed, clip_count);
+            assert!(variation.clip_index < clip_count);
+            assert_eq!(variation.clip_index, RatAnimationVariation::from_seed(seed, clip_count).clip_index);
+            picks[variation.clip_index] += 1;
+        }
+
+        // Roughly even, so the crowd doesn't all play the same clip
+        for count in picks {
+            assert!((400..600).contains(&count), "{picks:?}");
+        }
+    }
+
+    #[test]
+    fn variations_without_clips_use_the_first() {
+        for seed in 0..100 {
+            assert_eq!(RatAnimationVariation::from_seed(seed, 0).clip_index, 0);
+            assert_eq!(RatAnimationVariation::from_seed(seed, 1).clip_index, 0);
+        }
+    }
+
+    #[test]
+    fn clip_indices_wrap_onto_registered_nodes() {
+        let mut graph = AnimationGraph::new();
+        let root = graph.root;
+        let node_indices: Vec<_> = (0..3).map(|_| graph.add_blend(1.0, root)).collect();
+        let animations = Animations {
+            graph: Handle::default(),
+            node_indices: node_indices.clone(),
+        };
+
+        for (clip_index, &node) in node_indices.iter().enumerate() {
+            assert_eq!(animations.node(clip_index), node);
+        }
+        assert_eq!(animations.node(4), node_indices[1]);
+    }
+}
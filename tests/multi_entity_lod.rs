@@ -0,0 +1,96 @@
+//! Runs `setup_multi_entity_lod_example_app` headless and checks both entity
+//! types share the `LODProcessingBudget` by their plugin weights
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use hairyrat::lod_system::examples::{Cheese, Rat, setup_multi_entity_lod_example_app};
+use hairyrat::lod_system::*;
+use std::time::Duration;
+
+const RATS: usize = 200;
+const CHEESES: usize = 200;
+
+fn multi_entity_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, TransformPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)));
+    setup_multi_entity_lod_example_app(&mut app);
+    app
+}
+
+/// Spawns every entity at level 0 but far enough away to want level 1
+fn spawn_crowd(app: &mut App) {
+    let world = app.world_mut();
+    world.spawn((Camera::default(), Transform::IDENTITY));
+    for i in 0..RATS {
+        world.spawn((
+            Rat,
+            Transform::from_xyz(i as f32 * 0.01, 0.0, -15.0),
+            LODDistance::default(),
+            LODState::new(create_standard_lod_levels()[0]),
+            AnimationLODData::default(),
+        ));
+    }
+    for i in 0..CHEESES {
+        world.spawn((
+            Cheese,
+            Transform::from_xyz(i as f32 * 0.01, 0.0, -10.0),
+            LODDistance::default(),
+            LODState::new(create_aggressive_lod_levels()[0]),
+            MeshSwapLODData::default(),
+        ));
+    }
+}
+
+fn levels<T: Component>(app: &mut App) -> Vec<u8> {
+    app.world_mut()
+        .query_filtered::<&LODState, With<T>>()
+        .iter(app.world())
+        .map(|state| state.current_level.level)
+        .collect()
+}
+
+fn assert_budget_split(app: &mut App) {
+    let budget = app.world().resource::<LODProcessingBudget>();
+    assert_eq!(budget.max_operations_per_frame, 100);
+    assert_eq!(budget.allowance::<Rat, AnimationLODStrategy>(), 75);
+    assert_eq!(budget.allowance::<Cheese, MeshSwapLODStrategy>(), 25);
+
+    // Once transitions become ready every frame is split 3:1 between them
+    spawn_crowd(app);
+    let mut frames = 0;
+    while app.world().resource::<LODProcessingBudget>().current_operations == 0 {
+        assert!(frames < 10, "no transition was ever applied");
+        app.update();
+        frames += 1;
+    }
+    for rat_operations in [75, 75, 50] {
+        let budget = app.world().resource::<LODProcessingBudget>();
+        assert_eq!(budget.operations::<Rat, AnimationLODStrategy>(), rat_operations);
+        assert_eq!(budget.operations::<Cheese, MeshSwapLODStrategy>(), 25);
+        app.update();
+    }
+
+    // The rats finished without taking over the cheeses' share
+    let rat_levels = levels::<Rat>(app);
+    let cheese_levels = levels::<Cheese>(app);
+    assert!(rat_levels.iter().all(|&level| level == 1));
+    assert_eq!(cheese_levels.iter().filter(|&&level| level == 1).count(), 100);
+}
+
+#[test]
+fn plugins_share_the_budget_by_weight() {
+    let mut app = multi_entity_app();
+    app.finish();
+    app.cleanup();
+    assert_budget_split(&mut app);
+}
+
+#[test]
+fn a_budget_inserted_after_the_plugins_keeps_their_shares() {
+    let mut app = multi_entity_app();
+    app.insert_resource(LODProcessingBudget::new(100));
+    app.finish();
+    app.cleanup();
+    assert_budget_split(&mut app);
+}